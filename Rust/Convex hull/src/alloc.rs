@@ -0,0 +1,124 @@
+//! Heap profiling of algorithm runs.
+//! With the `count-allocations` feature the process allocates through `CountingAllocator`, and
+//! `Snapshot` reports what a run allocated into its metrics. Without the feature a snapshot
+//! records nothing, so the default build keeps the system allocator's performance.
+
+use convexhull::metrics::Metrics;
+
+#[cfg(feature = "count-allocations")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    pub static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    pub static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+    pub static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+    pub static PEAK_LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Forwards to the system allocator while counting allocations and live bytes.
+    pub struct CountingAllocator;
+
+    impl CountingAllocator {
+        fn allocated(size: usize) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            BYTES_ALLOCATED.fetch_add(size, Ordering::Relaxed);
+            let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+            PEAK_LIVE_BYTES.fetch_max(live, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                CountingAllocator::allocated(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+                CountingAllocator::allocated(new_size);
+            }
+            new_ptr
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+}
+
+/// The allocator counters at the start of a run.
+pub struct Snapshot {
+    #[cfg(feature = "count-allocations")]
+    allocations: usize,
+    #[cfg(feature = "count-allocations")]
+    bytes_allocated: usize,
+    #[cfg(feature = "count-allocations")]
+    live_bytes: usize,
+}
+
+impl Snapshot {
+    /// Takes a snapshot and restarts peak tracking from the current live heap.
+    pub fn start() -> Self {
+        #[cfg(feature = "count-allocations")]
+        {
+            use std::sync::atomic::Ordering;
+            let live_bytes = counting::LIVE_BYTES.load(Ordering::Relaxed);
+            counting::PEAK_LIVE_BYTES.store(live_bytes, Ordering::Relaxed);
+            Snapshot {
+                allocations: counting::ALLOCATIONS.load(Ordering::Relaxed),
+                bytes_allocated: counting::BYTES_ALLOCATED.load(Ordering::Relaxed),
+                live_bytes,
+            }
+        }
+        #[cfg(not(feature = "count-allocations"))]
+        Snapshot {}
+    }
+
+    /// Counts the allocations, bytes allocated and peak heap growth since the snapshot.
+    /// Memory the algorithm returns, like the hull itself, is still live and counts towards the peak.
+    pub fn record(&self, metrics: &mut Metrics) {
+        #[cfg(feature = "count-allocations")]
+        {
+            use std::sync::atomic::Ordering;
+            // Read everything before recording, since registering the counters allocates too.
+            let allocations = counting::ALLOCATIONS.load(Ordering::Relaxed) - self.allocations;
+            let bytes_allocated = counting::BYTES_ALLOCATED.load(Ordering::Relaxed) - self.bytes_allocated;
+            let peak_heap_bytes = counting::PEAK_LIVE_BYTES.load(Ordering::Relaxed).saturating_sub(self.live_bytes);
+            metrics.count("allocations", allocations as i64);
+            metrics.count("bytes_allocated", bytes_allocated as i64);
+            metrics.count("peak_heap_bytes", peak_heap_bytes as i64);
+        }
+        #[cfg(not(feature = "count-allocations"))]
+        let _ = metrics;
+    }
+}
+
+#[cfg(all(test, feature = "count-allocations"))]
+mod tests {
+    use crate::alloc::Snapshot;
+    use convexhull::metrics::Metrics;
+
+    #[test]
+    fn counts_allocations_of_a_run() {
+        let snapshot = Snapshot::start();
+        let first: Vec<u64> = Vec::with_capacity(1000);
+        let second: Vec<u64> = Vec::with_capacity(500);
+        drop(first);
+        drop(second);
+        let mut metrics = Metrics::new();
+        snapshot.record(&mut metrics);
+        // The test harness may allocate on other threads while this runs.
+        assert!(metrics.counter("allocations") >= 2);
+        assert!(metrics.counter("bytes_allocated") >= 12_000);
+        assert!(metrics.counter("peak_heap_bytes") >= 12_000);
+    }
+}
@@ -0,0 +1,164 @@
+//! An approximate hull from the extreme points in `k` uniformly spaced directions.
+//! Finding them is a single parallel pass of `k` projections per point, with no sorting, and the
+//! hull of the at most `k` extreme points is cheap.
+//!
+//! Every point of the true hull that is extreme for a direction between two sampled directions
+//! lies in the triangle between their extreme points and the intersection of their supporting
+//! lines. The true hull therefore extends at most the largest height of those triangles outside
+//! the result, which is reported as the error bound. The angle at the apex of each triangle is
+//! π - 2π/k, so for an input of diameter D the bound is at most D·tan(π/k)/2, and much smaller
+//! for smooth hulls, whose edges between sampled directions are short.
+
+use std::f64::consts::PI;
+use rayon::prelude::*;
+use crate::metrics::Metrics;
+use crate::{dir, inc_ch, Point};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApproximateHull {
+    /// The hull of the extreme points, in the same order as `inc_ch` returns hulls.
+    pub hull: Vec<Point>,
+    /// How far any input point can be outside `hull`.
+    pub error_bound: f64,
+}
+
+/// The approximate hull from `directions` directions, which must be at least 3.
+pub fn approx_ch(points: &[Point], directions: usize, test_struct: &mut Metrics) -> ApproximateHull {
+    assert!(directions >= 3, "An approximate hull needs at least 3 directions");
+    test_struct.set_start_time();
+    let units: Vec<(f64, f64)> = (0..directions)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / directions as f64;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+    if points.is_empty() {
+        test_struct.set_end_time();
+        return ApproximateHull { hull: Vec::new(), error_bound: 0.0 };
+    }
+
+    // The projection and point of the extreme point of every direction, found per chunk and
+    // then merged.
+    let (projections, extremes) = points
+        .par_chunks(1 << 14)
+        .map(|chunk| {
+            let mut projections = vec![f64::NEG_INFINITY; directions];
+            let mut extremes = vec![chunk[0]; directions];
+            for &p in chunk {
+                let (x, y) = (p.x as f64, p.y as f64);
+                for ((projection, extreme), &(ux, uy)) in projections.iter_mut().zip(extremes.iter_mut()).zip(&units) {
+                    let candidate = x * ux + y * uy;
+                    if candidate > *projection {
+                        *projection = candidate;
+                        *extreme = p;
+                    }
+                }
+            }
+            (projections, extremes)
+        })
+        .reduce_with(|(mut projections, mut extremes), (other_projections, other_extremes)| {
+            for i in 0..directions {
+                if other_projections[i] > projections[i] {
+                    projections[i] = other_projections[i];
+                    extremes[i] = other_extremes[i];
+                }
+            }
+            (projections, extremes)
+        })
+        .unwrap();
+    test_struct.count("projections", (points.len() * directions) as i64);
+
+    let mut error_bound: f64 = 0.0;
+    for i in 0..directions {
+        let j = (i + 1) % directions;
+        let ((h1, p1), (h2, p2)) = ((projections[i], extremes[i]), (projections[j], extremes[j]));
+        if p1 == p2 {
+            continue;
+        }
+        let ((ux1, uy1), (ux2, uy2)) = (units[i], units[j]);
+        let det = ux1 * uy2 - uy1 * ux2;
+        let apex = ((h1 * uy2 - h2 * uy1) / det, (ux1 * h2 - ux2 * h1) / det);
+        error_bound = error_bound.max(line_distance(p1, p2, apex));
+    }
+
+    let mut hull_metrics = Metrics::new();
+    let hull = inc_ch(&extremes, &mut hull_metrics);
+    test_struct.merge_counters(&hull_metrics);
+    test_struct.set_end_time();
+    ApproximateHull { hull, error_bound }
+}
+
+fn line_distance(a: Point, b: Point, (x, y): (f64, f64)) -> f64 {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    ((dx * (y - a.y as f64) - dy * (x - a.x as f64)) / (dx * dx + dy * dy).sqrt()).abs()
+}
+
+fn segment_distance(a: Point, b: Point, p: Point) -> f64 {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    let (px, py) = ((p.x - a.x) as f64, (p.y - a.y) as f64);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 { 0.0 } else { ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0) };
+    ((px - t * dx).powi(2) + (py - t * dy).powi(2)).sqrt()
+}
+
+/// The distance from `point` to a hull in `inc_ch` order, or 0 if it is inside or on it.
+pub fn distance_outside(hull: &[Point], point: Point) -> f64 {
+    match hull.len() {
+        0 => f64::INFINITY,
+        1 => segment_distance(hull[0], hull[0], point),
+        2 => segment_distance(hull[0], hull[1], point),
+        n => {
+            let edges = (0..n).map(|i| (hull[i], hull[(i + 1) % n]));
+            if edges.clone().all(|(a, b)| dir(a, b, point) <= 0) {
+                return 0.0;
+            }
+            edges.map(|(a, b)| segment_distance(a, b, point)).fold(f64::INFINITY, f64::min)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::approx::{approx_ch, distance_outside};
+    use crate::metrics::Metrics;
+    use crate::{inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn true_hull_is_within_the_error_bound() {
+        let mut rng = rand::thread_rng();
+        for &directions in [3, 8, 64].iter() {
+            for _ in 0..20 {
+                let points: Vec<Point> = (0..2000).map(|_| {
+                    let angle = rng.gen_range(0.0, 2.0 * std::f64::consts::PI);
+                    let radius = 1000.0 * rng.gen_range(0.0f64, 1.0).sqrt();
+                    Point::new((radius * angle.cos()) as i64, (radius * angle.sin()) as i64)
+                }).collect();
+                let approximate = approx_ch(&points, directions, &mut Metrics::new());
+                let exact = inc_ch(&points, &mut Metrics::new());
+                // Extreme points are on the true hull, but may lie inside one of its edges.
+                assert!(approximate.hull.iter().all(|&p| distance_outside(&exact, p) == 0.0));
+                for &p in &exact {
+                    assert!(distance_outside(&approximate.hull, p) <= approximate.error_bound + 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn many_directions_find_every_vertex_of_a_small_hull() {
+        let points = [Point::new(0, 0), Point::new(10, 0), Point::new(10, 10), Point::new(0, 10), Point::new(5, 5)];
+        let approximate = approx_ch(&points, 16, &mut Metrics::new());
+        assert_eq!(approximate.hull, inc_ch(&points, &mut Metrics::new()));
+        assert!(approximate.error_bound < 1e-9);
+    }
+
+    #[test]
+    fn distance_outside_a_square() {
+        let square = [Point::new(0, 0), Point::new(0, 4), Point::new(4, 4), Point::new(4, 0)];
+        assert_eq!(distance_outside(&square, Point::new(2, 2)), 0.0);
+        assert_eq!(distance_outside(&square, Point::new(4, 2)), 0.0);
+        assert_eq!(distance_outside(&square, Point::new(7, 2)), 3.0);
+        assert_eq!(distance_outside(&square, Point::new(7, 8)), 5.0);
+    }
+}
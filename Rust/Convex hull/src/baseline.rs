@@ -0,0 +1,274 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use crate::stats::{Summary, t_p_value};
+
+/// Directory, relative to the working directory, that baselines are stored in.
+const BASELINE_DIR: &str = "baselines";
+
+/// Chance of reporting any change at all when nothing changed, over every metric compared.
+const SIGNIFICANCE: f64 = 0.05;
+
+/// The generated input a baseline was measured on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    pub generator: String,
+    pub points: i64,
+    pub seed: u64,
+    /// Whether the points were kept in generation order, duplicates included.
+    pub ordered: bool,
+}
+
+/// The statistics of one metric of one algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub algorithm: String,
+    pub metric: String,
+    pub samples: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Baseline {
+    pub dataset: Dataset,
+    /// The algorithms that were run, by their names in the entries.
+    pub algorithms: Vec<String>,
+    pub entries: Vec<Entry>,
+}
+
+impl Baseline {
+    pub fn path(name: &str) -> PathBuf {
+        PathBuf::from(BASELINE_DIR).join(format!("{}.csv", name))
+    }
+
+    pub fn save(&self, name: &str) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(BASELINE_DIR)?;
+        let path = Baseline::path(name);
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "# generator={} points={} seed={} ordered={} algorithms={}",
+                 self.dataset.generator, self.dataset.points, self.dataset.seed, self.dataset.ordered, self.algorithms.join(","))?;
+        writeln!(file, "algorithm,metric,samples,mean,std_dev")?;
+        for e in &self.entries {
+            writeln!(file, "{},{},{},{},{}", e.algorithm, e.metric, e.samples, e.mean, e.std_dev)?;
+        }
+        Ok(path)
+    }
+
+    pub fn load(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Baseline::path(name);
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read baseline {}: {}", path.display(), e))?;
+        Baseline::parse(&contents).map_err(|e| format!("Malformed baseline {}: {}", path.display(), e).into())
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines();
+        let header = lines.next().ok_or("missing dataset header")?;
+        let mut generator = None;
+        let mut points = None;
+        let mut seed = None;
+        // Baselines from before ordered datasets existed have no ordered field.
+        let mut ordered = false;
+        let mut algorithms = None;
+        for field in header.trim_start_matches('#').split_whitespace() {
+            match field.split_once('=') {
+                Some(("generator", v)) => generator = Some(v.to_string()),
+                Some(("points", v)) => points = Some(v.parse::<i64>().map_err(|e| e.to_string())?),
+                Some(("seed", v)) => seed = Some(v.parse::<u64>().map_err(|e| e.to_string())?),
+                Some(("ordered", v)) => ordered = v.parse::<bool>().map_err(|e| e.to_string())?,
+                Some(("algorithms", v)) => algorithms = Some(v.split(',').filter(|a| !a.is_empty()).map(String::from).collect()),
+                _ => return Err(format!("unknown dataset field {}", field)),
+            }
+        }
+        let dataset = Dataset {
+            generator: generator.ok_or("missing generator")?,
+            points: points.ok_or("missing point count")?,
+            seed: seed.ok_or("missing seed")?,
+            ordered,
+        };
+
+        let entries = lines
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() != 5 {
+                    return Err(format!("expected 5 fields in {}", line));
+                }
+                Ok(Entry {
+                    algorithm: fields[0].to_string(),
+                    metric: fields[1].to_string(),
+                    samples: fields[2].parse().map_err(|e| format!("{}: {}", line, e))?,
+                    mean: fields[3].parse().map_err(|e| format!("{}: {}", line, e))?,
+                    std_dev: fields[4].parse().map_err(|e| format!("{}: {}", line, e))?,
+                })
+            })
+            .collect::<Result<Vec<Entry>, String>>()?;
+        // Older baselines didn't list their algorithms, which are then the ones with entries.
+        let algorithms = algorithms.unwrap_or_else(|| {
+            let mut algorithms: Vec<String> = Vec::new();
+            for e in &entries {
+                if !algorithms.contains(&e.algorithm) {
+                    algorithms.push(e.algorithm.clone());
+                }
+            }
+            algorithms
+        });
+        Ok(Baseline { dataset, algorithms, entries })
+    }
+
+    pub fn find(&self, algorithm: &str, metric: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.algorithm == algorithm && e.metric == metric)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Verdict {
+    Unchanged,
+    Improved,
+    Regressed,
+}
+
+/// Compares new measurements with their baselines, each given with a `threshold`, a fraction of
+/// the baseline mean that it may move by unnoticed, so that tiny but consistent shifts in timing
+/// don't fail a run. Every metric is a cost, so an increase is a regression.
+///
+/// A metric that didn't vary on either side with at least two samples is deterministic, like the
+/// counts of an algorithm on a seeded dataset, and any change in it is reported. The others are
+/// compared by Welch's t-test, which needs at least two samples on either side and some variance.
+/// Since one of many metrics is likely to differ by chance, the tests are corrected together by
+/// Holm's method, which keeps the chance of any false report across all of them at 5%.
+pub fn compare_all(comparisons: &[(&Entry, &Summary, f64)]) -> Vec<Verdict> {
+    let direction = |difference: f64| if difference > 0.0 { Verdict::Regressed } else { Verdict::Improved };
+    let mut verdicts = vec![Verdict::Unchanged; comparisons.len()];
+    let mut tested = Vec::new();
+    for (i, &(old, new, threshold)) in comparisons.iter().enumerate() {
+        let difference = new.mean - old.mean;
+        if difference == 0.0 || difference.abs() <= threshold * old.mean.abs() {
+            continue;
+        }
+        let deterministic = old.samples > 1 && new.samples > 1 && old.std_dev == 0.0 && new.std_dev == 0.0;
+        if deterministic {
+            verdicts[i] = direction(difference);
+        } else if let Some(p) = welch_p_value(old, new) {
+            tested.push((p, i));
+        }
+    }
+
+    tested.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let count = tested.len();
+    for (rank, &(p, i)) in tested.iter().enumerate() {
+        if p > SIGNIFICANCE / (count - rank) as f64 {
+            break;
+        }
+        verdicts[i] = direction(comparisons[i].1.mean - comparisons[i].0.mean);
+    }
+    verdicts
+}
+
+/// The two-sided p-value of Welch's t-test for a difference in means, if there are enough samples
+/// and variance to tell.
+fn welch_p_value(old: &Entry, new: &Summary) -> Option<f64> {
+    if old.samples < 2 || new.samples < 2 {
+        return None;
+    }
+    let old_variance = old.std_dev.powi(2) / old.samples as f64;
+    let new_variance = new.std_dev.powi(2) / new.samples as f64;
+    let standard_error = (old_variance + new_variance).sqrt();
+    if standard_error == 0.0 {
+        return None;
+    }
+    let degrees_of_freedom = standard_error.powi(4)
+        / (old_variance.powi(2) / (old.samples - 1) as f64 + new_variance.powi(2) / (new.samples - 1) as f64);
+    Some(t_p_value((new.mean - old.mean) / standard_error, degrees_of_freedom))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baseline::{compare_all, Baseline, Dataset, Entry, Verdict};
+    use crate::stats::Summary;
+
+    fn compare(old: &Entry, new: &Summary, threshold: f64) -> Verdict {
+        compare_all(&[(old, new, threshold)])[0]
+    }
+
+    fn entry(samples: &[f64]) -> Entry {
+        let summary = Summary::new(samples);
+        Entry {
+            algorithm: String::from("Incremental"),
+            metric: String::from("time_ns"),
+            samples: summary.samples,
+            mean: summary.mean,
+            std_dev: summary.std_dev,
+        }
+    }
+
+    #[test]
+    fn baseline_round_trips() {
+        let baseline = Baseline {
+            dataset: Dataset { generator: String::from("circle"), points: 1000, seed: 42, ordered: true },
+            algorithms: vec![String::from("Incremental"), String::from("MBC")],
+            entries: vec![entry(&[1.0, 2.0, 3.0]), entry(&[10.0])],
+        };
+        let mut contents = String::new();
+        contents.push_str("# generator=circle points=1000 seed=42 ordered=true algorithms=Incremental,MBC\n");
+        contents.push_str("algorithm,metric,samples,mean,std_dev\n");
+        for e in &baseline.entries {
+            contents.push_str(&format!("{},{},{},{},{}\n", e.algorithm, e.metric, e.samples, e.mean, e.std_dev));
+        }
+        assert_eq!(Baseline::parse(&contents), Ok(baseline.clone()));
+
+        let unordered = contents.replace(" ordered=true", "");
+        assert_eq!(Baseline::parse(&unordered).unwrap().dataset, Dataset { ordered: false, ..baseline.dataset.clone() });
+        // Without the list, the algorithms are the ones with entries.
+        let unlisted = contents.replace(" algorithms=Incremental,MBC", "");
+        assert_eq!(Baseline::parse(&unlisted).unwrap().algorithms, vec![String::from("Incremental")]);
+    }
+
+    #[test]
+    fn noisy_timings_are_compared_with_welch() {
+        let old = entry(&[100.0, 102.0, 98.0, 101.0, 99.0, 100.0]);
+        let slower = Summary::new(&[120.0, 122.0, 118.0, 121.0, 119.0, 120.0]);
+        let faster = Summary::new(&[80.0, 82.0, 78.0, 81.0, 79.0, 80.0]);
+        let noisy = Summary::new(&[60.0, 160.0, 90.0, 150.0, 70.0, 140.0]);
+        assert_eq!(compare(&old, &slower, 0.05), Verdict::Regressed);
+        assert_eq!(compare(&old, &faster, 0.05), Verdict::Improved);
+        assert_eq!(compare(&old, &noisy, 0.05), Verdict::Unchanged);
+        assert_eq!(compare(&old, &slower, 0.5), Verdict::Unchanged);
+    }
+
+    #[test]
+    fn deterministic_counts_change_on_any_difference() {
+        let old = entry(&[3971.0, 3971.0]);
+        assert_eq!(compare(&old, &Summary::new(&[3971.0, 3971.0]), 0.0), Verdict::Unchanged);
+        assert_eq!(compare(&old, &Summary::new(&[3972.0, 3972.0]), 0.0), Verdict::Regressed);
+        assert_eq!(compare(&old, &Summary::new(&[3970.0, 3970.0]), 0.0), Verdict::Improved);
+        // A counter that varied is compared like a timing.
+        assert_eq!(compare(&entry(&[3971.0, 3975.0, 3969.0]), &Summary::new(&[3972.0, 3970.0, 3974.0]), 0.0), Verdict::Unchanged);
+    }
+
+    #[test]
+    fn single_samples_are_never_significant() {
+        assert_eq!(compare(&entry(&[100.0]), &Summary::new(&[200.0]), 0.05), Verdict::Unchanged);
+        assert_eq!(compare(&entry(&[100.0, 101.0, 99.0]), &Summary::new(&[200.0]), 0.05), Verdict::Unchanged);
+        assert_eq!(compare(&entry(&[100.0]), &Summary::new(&[200.0, 201.0]), 0.0), Verdict::Unchanged);
+    }
+
+    #[test]
+    fn many_metrics_are_corrected_together() {
+        // A difference that is significant on its own, with p under 3%.
+        let old = entry(&[100.0, 102.0, 98.0, 101.0, 99.0, 100.0]);
+        let new = Summary::new(&[102.0, 104.0, 100.0, 103.0, 101.0, 103.0]);
+        assert_eq!(compare(&old, &new, 0.0), Verdict::Regressed);
+        // Among ten metrics it could well be chance.
+        let comparisons = vec![(&old, &new, 0.0); 10];
+        assert!(compare_all(&comparisons).iter().all(|&v| v == Verdict::Unchanged));
+        // A far larger difference still stands out.
+        let slower = Summary::new(&[120.0, 122.0, 118.0, 121.0, 119.0, 120.0]);
+        let mut comparisons = vec![(&old, &new, 0.0); 9];
+        comparisons.push((&old, &slower, 0.0));
+        assert_eq!(compare_all(&comparisons)[9], Verdict::Regressed);
+        assert_eq!(compare_all(&comparisons)[0], Verdict::Unchanged);
+    }
+}
@@ -0,0 +1,294 @@
+//! The smallest circle enclosing a set of points, by Welzl's randomized algorithm.
+//! Only hull vertices can be on the smallest enclosing circle, so it is run on a hull, which
+//! is usually far smaller than the points. The circle through three integer points has a
+//! rational centre and squared radius, which are computed exactly in 128-bit integers as long
+//! as the points span less than `MAX_SPAN`.
+
+use std::fmt;
+use std::time::Instant;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::metrics::Metrics;
+use crate::Point;
+
+/// Points spanning this much or more in x or y have no circle, since testing whether a point is in
+/// it can take more than 128 bits. The terms of such a test are at most 12 times the cube of the
+/// span, and the sum of their squares has to stay below 2^127.
+pub const MAX_SPAN: i64 = 500_000;
+
+/// A fraction in lowest terms with a positive denominator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        assert!(denominator != 0, "a rational needs a nonzero denominator");
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Rational { numerator: numerator / divisor, denominator: denominator / divisor }
+    }
+
+    pub fn numerator(self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(self) -> i128 {
+        self.denominator
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// A circle with a rational centre and squared radius. The radius itself is usually irrational.
+#[derive(Copy, Clone)]
+pub struct Circle {
+    /// The point the disc is relative to, which keeps its numbers small.
+    origin: Point,
+    disc: Disc,
+}
+
+impl Circle {
+    pub fn centre(&self) -> (Rational, Rational) {
+        let Disc { x, y, d, .. } = self.disc;
+        (Rational::new(x + self.origin.x as i128 * d, d), Rational::new(y + self.origin.y as i128 * d, d))
+    }
+
+    pub fn radius_squared(&self) -> Rational {
+        Rational::new(self.disc.r, self.disc.d * self.disc.d)
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius_squared().to_f64().sqrt()
+    }
+
+    /// Whether the point is inside the circle or on it, decided exactly.
+    pub fn contains(&self, point: Point) -> bool {
+        self.disc.contains(((point.x - self.origin.x) as i128, (point.y - self.origin.y) as i128))
+    }
+}
+
+impl PartialEq for Circle {
+    fn eq(&self, other: &Self) -> bool {
+        self.centre() == other.centre() && self.radius_squared() == other.radius_squared()
+    }
+}
+
+impl Eq for Circle {}
+
+impl fmt::Debug for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (x, y) = self.centre();
+        f.debug_struct("Circle")
+            .field("centre", &format_args!("({}, {})", x, y))
+            .field("radius_squared", &format_args!("{}", self.radius_squared()))
+            .finish()
+    }
+}
+
+/// A circle during the search, relative to an origin point: its centre is (x/d, y/d) and its
+/// squared radius r/d^2, with d positive, so containment is a comparison of integers.
+#[derive(Debug, Copy, Clone)]
+struct Disc {
+    x: i128,
+    y: i128,
+    d: i128,
+    r: i128,
+}
+
+impl Disc {
+    fn point(a: (i128, i128)) -> Self {
+        Disc { x: a.0, y: a.1, d: 1, r: 0 }
+    }
+
+    /// The circle with `a` and `b` on opposite ends.
+    fn diametral(a: (i128, i128), b: (i128, i128)) -> Self {
+        let r = (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2);
+        Disc { x: a.0 + b.0, y: a.1 + b.1, d: 2, r }
+    }
+
+    /// The circle through three points, which must not be on a line.
+    fn circumscribed(a: (i128, i128), b: (i128, i128), c: (i128, i128)) -> Self {
+        let (bx, by, cx, cy) = (b.0 - a.0, b.1 - a.1, c.0 - a.0, c.1 - a.1);
+        let mut d = 2 * (bx * cy - by * cx);
+        debug_assert!(d != 0, "no circle goes through three points on a line");
+        let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+        let (mut x, mut y) = (cy * b2 - by * c2, bx * c2 - cx * b2);
+        if d < 0 {
+            d = -d;
+            x = -x;
+            y = -y;
+        }
+        Disc { x: x + a.0 * d, y: y + a.1 * d, d, r: (x * x + y * y) }
+    }
+
+    fn contains(&self, p: (i128, i128)) -> bool {
+        (p.0 * self.d - self.x).pow(2) + (p.1 * self.d - self.y).pow(2) <= self.r
+    }
+}
+
+/// The smallest circle enclosing the points, by Welzl's algorithm in its iterative form: the
+/// points are shuffled by `rng` and added one at a time, and a point outside the circle so far
+/// is on the new circle, which is found with the points before it the same way. This takes
+/// expected O(n) time. Coordinates are taken relative to the first point, and the computation
+/// is exact. There is no circle without points, or for points spanning `MAX_SPAN` or more.
+pub fn welzl<R: Rng>(points: &[Point], rng: &mut R, test_struct: &mut Metrics) -> Option<Circle> {
+    let origin = *points.first()?;
+    let span = |coordinate: fn(&Point) -> i64| {
+        let (min, max) = points.iter().map(coordinate).fold((i64::MAX, i64::MIN), |(min, max), c| (min.min(c), max.max(c)));
+        max as i128 - min as i128
+    };
+    if span(|p| p.x) >= MAX_SPAN as i128 || span(|p| p.y) >= MAX_SPAN as i128 {
+        return None;
+    }
+    let mut points: Vec<(i128, i128)> = points
+        .iter()
+        .map(|p| ((p.x - origin.x) as i128, (p.y - origin.y) as i128))
+        .collect();
+    points.shuffle(rng);
+
+    let mut tests = 0;
+    let mut circle = Disc::point(points[0]);
+    for i in 1..points.len() {
+        tests += 1;
+        if circle.contains(points[i]) {
+            continue;
+        }
+        circle = Disc::point(points[i]);
+        for j in 0..i {
+            tests += 1;
+            if circle.contains(points[j]) {
+                continue;
+            }
+            circle = Disc::diametral(points[i], points[j]);
+            for k in 0..j {
+                tests += 1;
+                if !circle.contains(points[k]) {
+                    circle = Disc::circumscribed(points[i], points[j], points[k]);
+                }
+            }
+        }
+    }
+    test_struct.count("circle_tests", tests);
+    Some(Circle { origin, disc: circle })
+}
+
+/// The smallest enclosing circle of the points, found by computing their hull with `algorithm`
+/// and running Welzl's algorithm on its vertices. Like `welzl`, there is none for points spanning
+/// `MAX_SPAN` or more.
+pub fn min_enclosing_circle(
+    points: &[Point],
+    algorithm: fn(&[Point], &mut Metrics) -> Vec<Point>,
+    test_struct: &mut Metrics,
+) -> Option<Circle> {
+    let phase = Instant::now();
+    let hull = algorithm(points, test_struct);
+    test_struct.end_phase("hull", phase);
+    // The hull algorithm started the clock, and stopping it after Welzl's algorithm times both.
+    let phase = Instant::now();
+    let circle = welzl(&hull, &mut rand::thread_rng(), test_struct);
+    test_struct.end_phase("welzl", phase);
+    test_struct.set_end_time();
+    circle
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circle::{min_enclosing_circle, welzl, Circle, Rational, MAX_SPAN};
+    use crate::metrics::Metrics;
+    use crate::{dc_ch, inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn rationals_are_in_lowest_terms() {
+        assert_eq!(Rational::new(6, -4), Rational::new(-3, 2));
+        assert_eq!(Rational::new(0, 5), Rational::new(0, 1));
+        assert_eq!(Rational::new(-9, -3).to_string(), "3");
+        assert_eq!(Rational::new(1, 3).to_string(), "1/3");
+    }
+
+    /// The circle's centre and squared radius as (numerator, denominator) pairs.
+    fn exact(circle: Circle) -> [(i128, i128); 3] {
+        let (x, y) = circle.centre();
+        let r = circle.radius_squared();
+        [(x.numerator(), x.denominator()), (y.numerator(), y.denominator()), (r.numerator(), r.denominator())]
+    }
+
+    #[test]
+    fn circles_of_few_points() {
+        let circle = |points: &[Point]| exact(welzl(points, &mut rand::thread_rng(), &mut Metrics::new()).unwrap());
+        assert_eq!(welzl(&[], &mut rand::thread_rng(), &mut Metrics::new()), None);
+        assert_eq!(circle(&[Point::new(3, 4)]), [(3, 1), (4, 1), (0, 1)]);
+        assert_eq!(circle(&[Point::new(0, 0), Point::new(3, 1)]), [(3, 2), (1, 2), (5, 2)]);
+        // An acute triangle's circle goes through all three corners.
+        assert_eq!(circle(&[Point::new(0, 0), Point::new(4, 0), Point::new(1, 3)]), [(2, 1), (1, 1), (5, 1)]);
+        // An obtuse triangle's is the circle over its longest side.
+        assert_eq!(circle(&[Point::new(0, 0), Point::new(10, 0), Point::new(4, 1)]), [(5, 1), (0, 1), (25, 1)]);
+        // Far from the origin, as the circle is found relative to its first point.
+        assert_eq!(circle(&[Point::new(1_000_000, 1_000_000), Point::new(1_000_003, 1_000_000), Point::new(1_000_001, 1_000_002)]),
+                   [(2_000_003, 2), (2_000_001, 2), (5, 2)]);
+        // Wider points could overflow, and have no circle.
+        let wide = [Point::new(0, 0), Point::new(MAX_SPAN - 1, 1), Point::new(1, -MAX_SPAN + 2)];
+        assert!(welzl(&wide, &mut rand::thread_rng(), &mut Metrics::new()).is_some());
+        assert_eq!(welzl(&[Point::new(0, 0), Point::new(MAX_SPAN, 1)], &mut rand::thread_rng(), &mut Metrics::new()), None);
+        assert_eq!(welzl(&[Point::new(i64::MIN, 0), Point::new(i64::MAX, 0)], &mut rand::thread_rng(), &mut Metrics::new()), None);
+    }
+
+    #[test]
+    fn circle_is_timed_with_the_hull() {
+        let points: Vec<Point> = (0..100_000).map(|i| Point::new(i % 1000, i / 1000)).collect();
+        let mut metrics = Metrics::new();
+        min_enclosing_circle(&points, inc_ch, &mut metrics).unwrap();
+        let steps: u128 = metrics.phases()
+            .iter()
+            .filter(|(name, _)| *name == "hull" || *name == "welzl")
+            .map(|(_, time)| time.as_nanos())
+            .sum();
+        assert!(metrics.time_elapsed().as_nanos() >= steps);
+    }
+
+    #[test]
+    fn circle_encloses_points_and_is_smallest() {
+        let mut rng = rand::thread_rng();
+        // The widest points span just under MAX_SPAN.
+        for &(count, range) in [(3, 10), (20, 100), (1_000, 100_000), (1_000, MAX_SPAN / 2)].iter() {
+            for _ in 0..50 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(-range, range), rng.gen_range(-range, range))).collect();
+                let circle = min_enclosing_circle(&points, inc_ch, &mut Metrics::new()).unwrap();
+                assert!(points.iter().all(|&p| circle.contains(p)));
+                // The smallest circle has two points on opposite ends, or three around it.
+                let (x, y) = circle.centre();
+                let on_circle: Vec<Point> = points.iter().copied().filter(|&p| {
+                    let distance = (p.x as f64 - x.to_f64()).powi(2) + (p.y as f64 - y.to_f64()).powi(2);
+                    (distance - circle.radius_squared().to_f64()).abs() < 1e-6 * range as f64 * range as f64
+                }).collect();
+                assert!(on_circle.len() >= 2, "{:?} for {:?}", circle, points);
+                assert_eq!(min_enclosing_circle(&points, dc_ch, &mut Metrics::new()), Some(circle));
+                assert_eq!(welzl(&points, &mut rng, &mut Metrics::new()), Some(circle));
+            }
+        }
+    }
+}
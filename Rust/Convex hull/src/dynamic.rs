@@ -0,0 +1,196 @@
+//! A hull that is kept up to date while points are inserted and removed.
+//! All points are kept in an ordered set, and the upper and lower hull chains in sorted vectors.
+//! Inserting a point outside the hull splices it into the chains and pops the neighbours it
+//! hides. Removing a hull vertex recomputes the chain between its neighbours from the points in
+//! that slab, which stay hull vertices. Points inside the hull only touch the set.
+
+use std::collections::BTreeSet;
+use crate::metrics::Metrics;
+use crate::{dir, Point};
+
+#[derive(Debug, Clone, Default)]
+pub struct DynamicHull {
+    points: BTreeSet<Point>,
+    /// The chain `inc_ch` returns first, from the smallest point to the largest.
+    upper: Vec<Point>,
+    /// The other chain, also from the smallest point to the largest.
+    lower: Vec<Point>,
+    metrics: Metrics,
+}
+
+impl DynamicHull {
+    pub fn new() -> Self {
+        DynamicHull::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The turns counted by every update so far.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Adds a point, returning whether it was new.
+    pub fn insert(&mut self, point: Point) -> bool {
+        if !self.points.insert(point) {
+            return false;
+        }
+        insert_into_chain(&mut self.upper, point, true, &mut self.metrics);
+        insert_into_chain(&mut self.lower, point, false, &mut self.metrics);
+        true
+    }
+
+    /// Removes a point, returning whether it was present.
+    pub fn remove(&mut self, point: Point) -> bool {
+        if !self.points.remove(&point) {
+            return false;
+        }
+        remove_from_chain(&mut self.upper, &self.points, point, true, &mut self.metrics);
+        remove_from_chain(&mut self.lower, &self.points, point, false, &mut self.metrics);
+        true
+    }
+
+    /// Whether the point is inside or on the boundary of the hull.
+    pub fn contains(&self, point: Point) -> bool {
+        let (first, last) = match (self.upper.first(), self.upper.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return false,
+        };
+        if point < first || point > last {
+            return false;
+        }
+        !outside_chain(&self.upper, point, true) && !outside_chain(&self.lower, point, false)
+    }
+
+    /// The hull in the same order as `inc_ch` returns it.
+    pub fn hull(&self) -> Vec<Point> {
+        let mut hull = self.upper.clone();
+        if self.lower.len() > 2 {
+            hull.extend(self.lower[1..self.lower.len() - 1].iter().rev());
+        }
+        hull
+    }
+}
+
+/// Whether the orientation of `point` against an edge of the chain puts it strictly outside.
+fn is_outside(direction: i64, upper: bool) -> bool {
+    if upper { direction > 0 } else { direction < 0 }
+}
+
+/// Whether `point` is strictly outside the edge from `a` to `b` of the chain, counting the turn.
+/// The upper chain is the one `inc_ch` returns first.
+pub(crate) fn outside(a: Point, b: Point, point: Point, upper: bool, metrics: &mut Metrics) -> bool {
+    let direction = dir(a, b, point);
+    metrics.increment_turn(direction);
+    is_outside(direction, upper)
+}
+
+/// Whether `point`, which lies between the ends of the chain, is strictly outside of it.
+fn outside_chain(chain: &[Point], point: Point, upper: bool) -> bool {
+    let i = chain.partition_point(|&p| p < point);
+    chain[i] != point && is_outside(dir(chain[i - 1], chain[i], point), upper)
+}
+
+/// Builds the chain over points in ascending order, like `generate_upper_hull`.
+fn build_chain(points: impl Iterator<Item = Point>, upper: bool, metrics: &mut Metrics) -> Vec<Point> {
+    let mut chain: Vec<Point> = Vec::new();
+    for point in points {
+        while chain.len() >= 2 && !outside(chain[chain.len() - 2], point, chain[chain.len() - 1], upper, metrics) {
+            chain.pop();
+        }
+        chain.push(point);
+    }
+    chain
+}
+
+fn insert_into_chain(chain: &mut Vec<Point>, point: Point, upper: bool, metrics: &mut Metrics) {
+    let mut i = chain.partition_point(|&p| p < point);
+    if i > 0 && i < chain.len() && !outside(chain[i - 1], chain[i], point, upper, metrics) {
+        return;
+    }
+    chain.insert(i, point);
+    while i >= 2 && !outside(chain[i - 2], chain[i], chain[i - 1], upper, metrics) {
+        chain.remove(i - 1);
+        i -= 1;
+    }
+    while i + 2 < chain.len() && !outside(chain[i], chain[i + 2], chain[i + 1], upper, metrics) {
+        chain.remove(i + 1);
+    }
+}
+
+fn remove_from_chain(chain: &mut Vec<Point>, points: &BTreeSet<Point>, point: Point, upper: bool, metrics: &mut Metrics) {
+    let i = match chain.binary_search(&point) {
+        Ok(i) => i,
+        Err(_) => return,
+    };
+    let (first, last) = match (points.iter().next(), points.iter().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return chain.clear(),
+    };
+    // The neighbours stay on the chain, and at its ends the new extreme points take over.
+    let from = if i > 0 { chain[i - 1] } else { first };
+    let to = if i + 1 < chain.len() { chain[i + 1] } else { last };
+    let replacement = build_chain(points.range(from..=to).copied(), upper, metrics);
+    chain.splice(i.saturating_sub(1)..=usize::min(i + 1, chain.len() - 1), replacement);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dynamic::DynamicHull;
+    use crate::metrics::Metrics;
+    use crate::{inc_ch, Point};
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+
+    #[test]
+    fn updates_match_batch_hulls() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let mut hull = DynamicHull::new();
+            let mut present: Vec<Point> = Vec::new();
+            for _ in 0..200 {
+                if present.is_empty() || rng.gen_bool(0.6) {
+                    let point = Point::new(rng.gen_range(0, 15), rng.gen_range(0, 15));
+                    assert_eq!(hull.insert(point), !present.contains(&point));
+                    if !present.contains(&point) {
+                        present.push(point);
+                    }
+                } else {
+                    let point = *present.choose(&mut rng).unwrap();
+                    present.retain(|&p| p != point);
+                    assert!(hull.remove(point));
+                    assert!(!hull.remove(point));
+                }
+                let expected = inc_ch(&present, &mut Metrics::new());
+                if expected.len() >= 3 {
+                    assert_eq!(hull.hull(), expected, "hull of {:?}", present);
+                }
+                assert_eq!(hull.len(), present.len());
+            }
+        }
+    }
+
+    #[test]
+    fn contains_points_inside_and_on_the_hull() {
+        let mut hull = DynamicHull::new();
+        assert!(!hull.contains(Point::new(0, 0)));
+        for &point in [Point::new(0, 0), Point::new(4, 0), Point::new(4, 4), Point::new(0, 4)].iter() {
+            hull.insert(point);
+        }
+        assert!(hull.contains(Point::new(2, 2)));
+        assert!(hull.contains(Point::new(0, 2)));
+        assert!(hull.contains(Point::new(4, 4)));
+        assert!(!hull.contains(Point::new(5, 2)));
+        assert!(!hull.contains(Point::new(2, -1)));
+        hull.remove(Point::new(4, 4));
+        assert!(hull.contains(Point::new(1, 1)));
+        assert!(!hull.contains(Point::new(3, 3)));
+        assert_eq!(hull.hull(), vec![Point::new(0, 0), Point::new(0, 4), Point::new(4, 0)]);
+    }
+}
@@ -0,0 +1,74 @@
+//! A hull of point streams too large to hold in memory.
+//! The stream is read in chunks, and every chunk is merged into the hull so far by taking the
+//! hull of both. At most a chunk and a hull are in memory at a time. Duplicates don't need to be
+//! removed first, since they are only a cost to the hull of their chunk.
+
+use std::io::{self, BufRead};
+use std::time::Instant;
+use crate::io::read_points;
+use crate::metrics::Metrics;
+use crate::{inc_ch, Point};
+
+/// The hull of the points in a text stream, in the same order as `inc_ch` returns it.
+/// The stream is read `chunk_size` points at a time, and the amount of points read and chunks
+/// are counted as `points_read` and `chunks`.
+pub fn external_ch<R: BufRead>(reader: R, chunk_size: usize, test_struct: &mut Metrics) -> io::Result<Vec<Point>> {
+    assert!(chunk_size > 0, "Chunks must hold at least one point");
+    test_struct.set_start_time();
+    let mut hull: Vec<Point> = Vec::new();
+    let mut chunk: Vec<Point> = Vec::with_capacity(chunk_size);
+    let mut points = read_points(reader);
+    loop {
+        let phase = Instant::now();
+        chunk.clear();
+        chunk.extend_from_slice(&hull);
+        let hull_len = chunk.len();
+        for point in points.by_ref().take(chunk_size) {
+            chunk.push(point?);
+        }
+        test_struct.end_phase("read", phase);
+        let read = chunk.len() - hull_len;
+        if read == 0 {
+            break;
+        }
+        test_struct.count("points_read", read as i64);
+        test_struct.count("chunks", 1);
+
+        let phase = Instant::now();
+        let mut chunk_metrics = Metrics::new();
+        hull = inc_ch(&chunk, &mut chunk_metrics);
+        test_struct.merge_counters(&chunk_metrics);
+        test_struct.end_phase("merge", phase);
+    }
+    test_struct.set_end_time();
+    Ok(hull)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::external::external_ch;
+    use crate::io::write_points;
+    use crate::metrics::Metrics;
+    use crate::{inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn chunked_hull_matches_inc_ch() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..5000).map(|_| Point::new(rng.gen_range(0, 500), rng.gen_range(0, 500))).collect();
+        let mut text = Vec::new();
+        write_points(&mut text, &points).unwrap();
+        let expected = inc_ch(&points, &mut Metrics::new());
+        for &chunk_size in [1, 7, 1000, 10_000].iter() {
+            let mut metrics = Metrics::new();
+            assert_eq!(external_ch(&text[..], chunk_size, &mut metrics).unwrap(), expected);
+            assert_eq!(metrics.counter("points_read"), 5000);
+            assert_eq!(metrics.counter("chunks"), 5000_usize.div_ceil(chunk_size) as i64);
+        }
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        assert!(external_ch("1 2\n3 4\nfive 6\n".as_bytes(), 2, &mut Metrics::new()).is_err());
+    }
+}
@@ -0,0 +1,484 @@
+//! A computed hull, its area, perimeter and moments, and measurements of it by rotating calipers.
+//! The calipers are pairs of parallel supporting lines that rotate around the hull. One of each
+//! pair of an optimal width or enclosing rectangle lies on an edge, so it is enough to put a
+//! caliper on every edge in turn. The vertices that touch the other calipers only move forwards
+//! as the edges go around, so every measurement takes O(h) in total.
+
+use crate::{dir, Point};
+
+/// The vertices of a convex polygon, in the same order as `inc_ch` returns hulls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hull {
+    vertices: Vec<Point>,
+}
+
+/// The distance between the closest pair of parallel lines with the hull between them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Width {
+    pub width: f64,
+    /// The edge of the hull that one of the lines is on.
+    pub edge: (Point, Point),
+    /// The vertex farthest from that edge, which the other line goes through.
+    pub opposite: Point,
+}
+
+/// A rectangle enclosing the hull, with one side on an edge of the hull.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rectangle {
+    /// The corners, counter-clockwise, starting with the two on the line through the edge.
+    pub corners: [(f64, f64); 4],
+    /// The length of the sides parallel to the edge.
+    pub length: f64,
+    /// The length of the sides perpendicular to the edge.
+    pub height: f64,
+}
+
+impl Rectangle {
+    pub fn area(&self) -> f64 {
+        self.length * self.height
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        2.0 * (self.length + self.height)
+    }
+}
+
+/// The second moments of the area of a hull, about axes through its centroid.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Moments {
+    /// The integral of (x - cx)^2 over the area.
+    pub xx: f64,
+    /// The integral of (y - cy)^2 over the area.
+    pub yy: f64,
+    /// The integral of (x - cx)(y - cy) over the area.
+    pub xy: f64,
+}
+
+impl Hull {
+    /// A hull of vertices in either direction around the polygon. Repeated and collinear
+    /// vertices are dropped, and the rest are rotated and ordered like `inc_ch` orders them,
+    /// so the hull of any of the algorithms, or of `gift_ch` with its collinear points, works.
+    pub fn new(vertices: Vec<Point>) -> Self {
+        let mut vertices = vertices;
+        vertices.dedup();
+        while vertices.len() > 1 && vertices[0] == vertices[vertices.len() - 1] {
+            vertices.pop();
+        }
+        if vertices.len() >= 3 {
+            let mut corners: Vec<Point> = Vec::with_capacity(vertices.len());
+            let n = vertices.len();
+            for i in 0..n {
+                if dir(vertices[(i + n - 1) % n], vertices[i], vertices[(i + 1) % n]) != 0 {
+                    corners.push(vertices[i]);
+                }
+            }
+            // A polygon with all vertices on a line is that line's segment.
+            vertices = match corners.len() {
+                0 => vec![*vertices.iter().min().unwrap(), *vertices.iter().max().unwrap()],
+                _ => corners,
+            };
+        }
+        if vertices.len() >= 3 && dir(vertices[0], vertices[1], vertices[2]) > 0 {
+            vertices.reverse();
+        }
+        let start = (0..vertices.len()).min_by_key(|&i| vertices[i]).unwrap_or(0);
+        vertices.rotate_left(start);
+        Hull { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// The edges in order, from each vertex to the next, closing back to the first.
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    /// Twice the area, by the shoelace formula in 128-bit integers. The area of a polygon with
+    /// integer vertices can be a half, but twice it is an integer and exact.
+    pub fn twice_area(&self) -> i128 {
+        // The vertices go clockwise, which makes the shoelace sum negative.
+        -self.edges().map(|(a, b)| cross(a, b)).sum::<i128>()
+    }
+
+    pub fn area(&self) -> f64 {
+        self.twice_area() as f64 / 2.0
+    }
+
+    /// The length of the boundary. A segment's boundary goes there and back, so it is twice
+    /// the segment's length.
+    pub fn perimeter(&self) -> f64 {
+        self.edges().map(|(a, b)| (distance_squared(a, b) as f64).sqrt()).sum()
+    }
+
+    /// The centroid of the area. A hull without area has the midpoint of its vertices instead,
+    /// which is the midpoint of a segment or the single point.
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        let origin = *self.vertices.first()?;
+        let last = self.vertices[self.len() - 1];
+        if self.len() < 3 {
+            return Some(((origin.x + last.x) as f64 / 2.0, (origin.y + last.y) as f64 / 2.0));
+        }
+        // Relative to the first vertex the sums stay small, and they are exact until the final
+        // division. Each edge adds the triangle it makes with the first vertex.
+        let relative = |p: Point| Point::new(p.x - origin.x, p.y - origin.y);
+        let (mut x, mut y, mut twice_area) = (0, 0, 0);
+        for (a, b) in self.edges().map(|(a, b)| (relative(a), relative(b))) {
+            let c = cross(a, b);
+            x += (a.x + b.x) as i128 * c;
+            y += (a.y + b.y) as i128 * c;
+            twice_area += c;
+        }
+        let denominator = 3.0 * twice_area as f64;
+        Some((origin.x as f64 + x as f64 / denominator, origin.y as f64 + y as f64 / denominator))
+    }
+
+    /// The second moments of the area about the centroid, which are 0 for a hull without area.
+    /// Coordinates are taken relative to the centroid, so there is no cancellation between the
+    /// moments about the origin and the shift to the centroid.
+    pub fn second_moments(&self) -> Option<Moments> {
+        let (cx, cy) = self.centroid()?;
+        let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+        for (a, b) in self.edges() {
+            let (ax, ay, bx, by) = (a.x as f64 - cx, a.y as f64 - cy, b.x as f64 - cx, b.y as f64 - cy);
+            // Twice the signed area of the triangle of the edge and the centroid, negative as the
+            // edges go clockwise.
+            let c = ax * by - bx * ay;
+            xx += c * (ax * ax + ax * bx + bx * bx);
+            yy += c * (ay * ay + ay * by + by * by);
+            xy += c * (ax * by + 2.0 * ax * ay + 2.0 * bx * by + bx * ay);
+        }
+        Some(Moments { xx: -xx / 12.0, yy: -yy / 12.0, xy: -xy / 24.0 })
+    }
+
+    /// The vertices counter-clockwise, the direction the calipers rotate in.
+    fn counter_clockwise(&self) -> Vec<Point> {
+        self.vertices.iter().rev().copied().collect()
+    }
+
+    /// The farthest pair of points of the hull, which is a pair of vertices that supporting
+    /// lines through them can be parallel at, an antipodal pair. Every such pair is met as a
+    /// vertex of an edge and the vertex farthest from it, or one of two farthest vertices.
+    pub fn diameter(&self) -> Option<(Point, Point)> {
+        let v = self.counter_clockwise();
+        let n = v.len();
+        match n {
+            0 => return None,
+            1 => return Some((v[0], v[0])),
+            2 => return Some((self.vertices[0], self.vertices[1])),
+            _ => (),
+        }
+        let mut best = (v[0], v[1]);
+        let consider = |a: Point, b: Point, best: &mut (Point, Point)| {
+            if distance_squared(a, b) > distance_squared(best.0, best.1) {
+                *best = (a, b);
+            }
+        };
+        let mut k = 1;
+        for i in 0..n {
+            let (a, b) = (v[i], v[(i + 1) % n]);
+            while dir(a, b, v[(k + 1) % n]) > dir(a, b, v[k]) {
+                k = (k + 1) % n;
+            }
+            consider(a, v[k], &mut best);
+            consider(b, v[k], &mut best);
+            // An edge parallel to this one has two farthest vertices.
+            if dir(a, b, v[(k + 1) % n]) == dir(a, b, v[k]) {
+                consider(a, v[(k + 1) % n], &mut best);
+                consider(b, v[(k + 1) % n], &mut best);
+            }
+        }
+        Some(best)
+    }
+
+    /// The length of the diameter.
+    pub fn diameter_length(&self) -> Option<f64> {
+        self.diameter().map(|(a, b)| (distance_squared(a, b) as f64).sqrt())
+    }
+
+    /// The minimum width. Hulls of fewer than three vertices have width 0.
+    pub fn width(&self) -> Option<Width> {
+        let v = self.counter_clockwise();
+        match v.len() {
+            0 => None,
+            1 => Some(Width { width: 0.0, edge: (v[0], v[0]), opposite: v[0] }),
+            2 => Some(Width { width: 0.0, edge: (v[0], v[1]), opposite: v[0] }),
+            _ => self
+                .calipers()
+                .map(|c| Width { width: c.height as f64 / c.edge_length, edge: (c.a, c.b), opposite: c.farthest })
+                .min_by(|a, b| a.width.partial_cmp(&b.width).unwrap()),
+        }
+    }
+
+    /// The enclosing rectangle of the smallest area.
+    pub fn min_area_rectangle(&self) -> Option<Rectangle> {
+        self.min_rectangle_by(|c| c.length as f64 * c.height as f64 / (c.edge_length * c.edge_length))
+    }
+
+    /// The enclosing rectangle of the smallest perimeter.
+    pub fn min_perimeter_rectangle(&self) -> Option<Rectangle> {
+        self.min_rectangle_by(|c| (c.length + c.height) as f64 / c.edge_length)
+    }
+
+    fn min_rectangle_by<F: Fn(&Calipers) -> f64>(&self, cost: F) -> Option<Rectangle> {
+        match self.len() {
+            0 => None,
+            1 | 2 => {
+                let (a, b) = (self.vertices[0], self.vertices[self.len() - 1]);
+                let corners = [(a.x as f64, a.y as f64), (b.x as f64, b.y as f64), (b.x as f64, b.y as f64), (a.x as f64, a.y as f64)];
+                Some(Rectangle { corners, length: (distance_squared(a, b) as f64).sqrt(), height: 0.0 })
+            }
+            _ => self
+                .calipers()
+                .map(|c| (cost(&c), c))
+                .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+                .map(|(_, c)| c.rectangle()),
+        }
+    }
+
+    /// The calipers on every edge of a hull of at least three vertices.
+    fn calipers(&self) -> impl Iterator<Item = Calipers> {
+        let v = self.counter_clockwise();
+        let n = v.len();
+        let along = |a: Point, b: Point, p: Point| (b.x - a.x) * (p.x - a.x) + (b.y - a.y) * (p.y - a.y);
+        // Going counter-clockwise from an edge, the vertices first get farther along it, then
+        // farther from it, and then farther back along it.
+        let (mut front, mut farthest, mut back) = (1, 1, 1);
+        let mut first = true;
+        (0..n).map(move |i| {
+            let (a, b) = (v[i], v[(i + 1) % n]);
+            let next = |j: usize| (j + 1) % n;
+            if first {
+                front = next(i);
+            }
+            while along(a, b, v[next(front)]) > along(a, b, v[front]) {
+                front = next(front);
+            }
+            if first {
+                farthest = front;
+            }
+            while dir(a, b, v[next(farthest)]) > dir(a, b, v[farthest]) {
+                farthest = next(farthest);
+            }
+            if first {
+                back = farthest;
+                first = false;
+            }
+            while along(a, b, v[next(back)]) < along(a, b, v[back]) {
+                back = next(back);
+            }
+            Calipers {
+                a,
+                b,
+                farthest: v[farthest],
+                edge_length: (distance_squared(a, b) as f64).sqrt(),
+                back: along(a, b, v[back]),
+                length: along(a, b, v[front]) - along(a, b, v[back]),
+                height: dir(a, b, v[farthest]),
+            }
+        })
+    }
+}
+
+impl From<Vec<Point>> for Hull {
+    fn from(vertices: Vec<Point>) -> Self {
+        Hull::new(vertices)
+    }
+}
+
+/// The calipers on the edge from `a` to `b`. Lengths are scaled by the length of the edge, so
+/// they stay exact until a measurement is taken.
+struct Calipers {
+    a: Point,
+    b: Point,
+    farthest: Point,
+    edge_length: f64,
+    /// How far back along the edge, from `a`, the hull reaches. Not positive.
+    back: i64,
+    length: i64,
+    height: i64,
+}
+
+impl Calipers {
+    fn rectangle(&self) -> Rectangle {
+        let d = self.edge_length;
+        let (ux, uy) = ((self.b.x - self.a.x) as f64 / d, (self.b.y - self.a.y) as f64 / d);
+        // Counter-clockwise, the hull is left of its edges.
+        let (nx, ny) = (-uy, ux);
+        let (start, length, height) = (self.back as f64 / d, self.length as f64 / d, self.height as f64 / d);
+        let corner = |along: f64, up: f64| (self.a.x as f64 + ux * along + nx * up, self.a.y as f64 + uy * along + ny * up);
+        Rectangle {
+            corners: [corner(start, 0.0), corner(start + length, 0.0), corner(start + length, height), corner(start, height)],
+            length,
+            height,
+        }
+    }
+}
+
+fn distance_squared(a: Point, b: Point) -> i64 {
+    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+}
+
+/// The cross product of `a` and `b` as vectors from the origin, which is twice the signed area of
+/// the triangle they make with it.
+fn cross(a: Point, b: Point) -> i128 {
+    a.x as i128 * b.y as i128 - b.x as i128 * a.y as i128
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hull::{distance_squared, Hull, Moments};
+    use crate::metrics::Metrics;
+    use crate::{dir, gift_ch, inc_ch, Point};
+    use rand::Rng;
+
+    /// The width and the smallest area and perimeter of rectangles on each edge, by trying every
+    /// vertex against every edge.
+    fn brute_force(hull: &[Point]) -> (f64, f64, f64) {
+        let n = hull.len();
+        let (mut width, mut area, mut perimeter) = (f64::MAX, f64::MAX, f64::MAX);
+        for i in 0..n {
+            let (a, b) = (hull[i], hull[(i + 1) % n]);
+            let d = (distance_squared(a, b) as f64).sqrt();
+            let along: Vec<f64> = hull.iter().map(|p| ((b.x - a.x) * (p.x - a.x) + (b.y - a.y) * (p.y - a.y)) as f64 / d).collect();
+            let height = hull.iter().map(|&p| dir(a, b, p).abs()).max().unwrap() as f64 / d;
+            let length = along.iter().cloned().fold(f64::MIN, f64::max) - along.iter().cloned().fold(f64::MAX, f64::min);
+            width = width.min(height);
+            area = area.min(length * height);
+            perimeter = perimeter.min(2.0 * (length + height));
+        }
+        (width, area, perimeter)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * f64::max(1.0, b.abs())
+    }
+
+    #[test]
+    fn calipers_match_brute_force() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(3, 5), (10, 10), (50, 100), (1_000, 1_000_000)].iter() {
+            for _ in 0..100 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let vertices = inc_ch(&points, &mut Metrics::new());
+                if vertices.len() < 3 {
+                    continue;
+                }
+                let hull = Hull::new(vertices.clone());
+                assert_eq!(hull.vertices(), &vertices[..]);
+
+                let (a, b) = hull.diameter().unwrap();
+                let farthest = points.iter().flat_map(|&p| points.iter().map(move |&q| distance_squared(p, q))).max().unwrap();
+                assert_eq!(distance_squared(a, b), farthest, "points {:?}", points);
+
+                let (width, area, perimeter) = brute_force(&vertices);
+                assert!(close(hull.width().unwrap().width, width), "points {:?}", points);
+                let rectangle = hull.min_area_rectangle().unwrap();
+                assert!(close(rectangle.area(), area), "{} != {} for {:?}", rectangle.area(), area, points);
+                assert!(close(hull.min_perimeter_rectangle().unwrap().perimeter(), perimeter), "points {:?}", points);
+                // Every point is inside the rectangle, allowing for rounding.
+                let c = rectangle.corners;
+                for p in &points {
+                    for i in 0..4 {
+                        let (a, b) = (c[i], c[(i + 1) % 4]);
+                        let side = (b.0 - a.0) * (p.y as f64 - a.1) - (b.1 - a.1) * (p.x as f64 - a.0);
+                        assert!(side >= -1e-6 * range as f64 * range as f64, "{:?} outside {:?}", p, c);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn measures_of_a_rotated_square() {
+        // A square with side 10 at an angle, with a collinear point as gift_ch can return it.
+        let hull = Hull::new(vec![Point::new(0, 0), Point::new(-8, 6), Point::new(-2, 14), Point::new(6, 8), Point::new(3, 4)]);
+        assert_eq!(hull.vertices(), &[Point::new(-8, 6), Point::new(-2, 14), Point::new(6, 8), Point::new(0, 0)]);
+        assert_eq!(hull.diameter_length(), Some((200f64).sqrt()));
+        assert!(close(hull.width().unwrap().width, 10.0));
+        let rectangle = hull.min_area_rectangle().unwrap();
+        assert!(close(rectangle.area(), 100.0));
+        assert!(close(hull.min_perimeter_rectangle().unwrap().perimeter(), 40.0));
+        for corner in &rectangle.corners {
+            assert!(hull.vertices().iter().any(|v| (corner.0 - v.x as f64).abs() < 1e-9 && (corner.1 - v.y as f64).abs() < 1e-9), "{:?}", corner);
+        }
+    }
+
+    #[test]
+    fn any_direction_and_degenerate_hulls() {
+        let points: Vec<Point> = (0..100).map(|i| Point::new(i % 10 * 3, i / 10 * 7)).collect();
+        let vertices = inc_ch(&points, &mut Metrics::new());
+        let mut reversed = gift_ch(&points, &mut Metrics::new());
+        reversed.reverse();
+        assert_eq!(Hull::new(reversed).vertices(), &vertices[..]);
+
+        assert_eq!(Hull::new(Vec::new()).diameter(), None);
+        assert_eq!(Hull::new(vec![Point::new(1, 1)]).width().unwrap().width, 0.0);
+        let segment = Hull::new(vec![Point::new(0, 0), Point::new(1, 1), Point::new(3, 3)]);
+        assert_eq!(segment.vertices(), &[Point::new(0, 0), Point::new(3, 3)]);
+        assert_eq!(segment.diameter(), Some((Point::new(0, 0), Point::new(3, 3))));
+        assert_eq!(segment.min_area_rectangle().unwrap().area(), 0.0);
+    }
+
+    #[test]
+    fn area_perimeter_centroid_and_moments() {
+        // The rotated square with side 10 again, and a right triangle with legs 6 and 3.
+        let square = Hull::new(vec![Point::new(0, 0), Point::new(-8, 6), Point::new(-2, 14), Point::new(6, 8)]);
+        assert_eq!(square.twice_area(), 200);
+        assert!(close(square.perimeter(), 40.0));
+        assert_eq!(square.centroid(), Some((-1.0, 7.0)));
+        // A square's moments are the same about every axis through its centre.
+        let moments = square.second_moments().unwrap();
+        assert!(close(moments.xx, 10_000.0 / 12.0) && close(moments.yy, 10_000.0 / 12.0) && moments.xy.abs() < 1e-9, "{:?}", moments);
+
+        let triangle = Hull::new(vec![Point::new(0, 0), Point::new(6, 0), Point::new(0, 3)]);
+        assert_eq!(triangle.twice_area(), 18);
+        assert!(close(triangle.perimeter(), 9.0 + 45f64.sqrt()));
+        assert_eq!(triangle.centroid(), Some((2.0, 1.0)));
+        let Moments { xx, yy, xy } = triangle.second_moments().unwrap();
+        assert!(close(xx, 18.0) && close(yy, 4.5) && close(xy, -4.5), "{:?}", (xx, yy, xy));
+
+        // Half an integer area, far from the origin.
+        let far = Hull::new(vec![Point::new(1 << 40, 1 << 40), Point::new((1 << 40) + 1, 1 << 40), Point::new(1 << 40, (1 << 40) + 1)]);
+        assert_eq!(far.twice_area(), 1);
+        assert_eq!(far.area(), 0.5);
+
+        let segment = Hull::new(vec![Point::new(0, 0), Point::new(3, 4)]);
+        assert_eq!(segment.twice_area(), 0);
+        assert_eq!(segment.perimeter(), 10.0);
+        assert_eq!(segment.centroid(), Some((1.5, 2.0)));
+        assert_eq!(segment.second_moments(), Some(Moments { xx: 0.0, yy: 0.0, xy: 0.0 }));
+        assert_eq!(Hull::new(vec![Point::new(2, 5)]).centroid(), Some((2.0, 5.0)));
+        assert_eq!(Hull::new(Vec::new()).centroid(), None);
+        assert_eq!(Hull::new(Vec::new()).twice_area(), 0);
+    }
+
+    #[test]
+    fn area_and_centroid_match_a_triangulation() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let points: Vec<Point> = (0..50).map(|_| Point::new(rng.gen_range(-1_000, 1_000), rng.gen_range(-1_000, 1_000))).collect();
+            let hull = Hull::new(inc_ch(&points, &mut Metrics::new()));
+            let v = hull.vertices();
+            // A fan of triangles from the first vertex, which are clockwise like the hull.
+            let fan: Vec<(i64, (f64, f64))> = (1..v.len() - 1)
+                .map(|i| (-dir(v[0], v[i], v[i + 1]), ((v[0].x + v[i].x + v[i + 1].x) as f64 / 3.0, (v[0].y + v[i].y + v[i + 1].y) as f64 / 3.0)))
+                .collect();
+            let twice_area: i64 = fan.iter().map(|(a, _)| a).sum();
+            assert_eq!(hull.twice_area(), twice_area as i128);
+            let (x, y) = hull.centroid().unwrap();
+            let (fan_x, fan_y) = fan.iter().fold((0.0, 0.0), |(x, y), &(a, (cx, cy))| (x + a as f64 * cx, y + a as f64 * cy));
+            assert!(close(x, fan_x / twice_area as f64) && close(y, fan_y / twice_area as f64), "points {:?}", points);
+        }
+    }
+}
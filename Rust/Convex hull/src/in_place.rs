@@ -0,0 +1,160 @@
+//! Hull algorithms that reorder the caller's slice instead of allocating.
+//! They move the hull to the front of the slice, in the same order `inc_ch` returns it, and return
+//! its length. Nothing is cloned or collected, so they are safe to call where the heap is off limits.
+
+use crate::metrics::Metrics;
+use crate::{dir, Point};
+
+/// The incremental (monotone chain) algorithm in place.
+/// The first chain is built over the sorted slice by swapping each kept point down onto the
+/// stack, which leaves the discarded points behind it. Only those can be on the second chain, so
+/// they are sorted in descending order and the stack continues over them from the last point.
+pub fn inc_ch_in_place(points: &mut [Point], test_struct: &mut Metrics) -> usize {
+    test_struct.set_start_time();
+    points.sort_unstable();
+    let n = points.len();
+    if n < 2 || points[0] == points[n - 1] {
+        test_struct.set_end_time();
+        return usize::min(n, 1);
+    }
+
+    let mut top = 0;
+    for i in 0..n {
+        while top >= 2 && pops(points[top - 2], points[top - 1], points[i], test_struct) {
+            top -= 1;
+        }
+        points.swap(top, i);
+        top += 1;
+    }
+
+    // The second chain starts on the last point of the first one, which is the largest point.
+    let bottom = top - 1;
+    let discarded = top;
+    points[discarded..].sort_unstable_by(|a, b| b.cmp(a));
+    for i in discarded..n {
+        while top - bottom >= 2 && pops(points[top - 2], points[top - 1], points[i], test_struct) {
+            top -= 1;
+        }
+        points.swap(top, i);
+        top += 1;
+    }
+    // Close the hull on the smallest point, which is already at the front.
+    while top - bottom >= 2 && pops(points[top - 2], points[top - 1], points[0], test_struct) {
+        top -= 1;
+    }
+    test_struct.set_end_time();
+    top
+}
+
+/// Whether `b` leaves the chain when `c` is added, because a, b, c is not a left turn.
+fn pops(a: Point, b: Point, c: Point, test_struct: &mut Metrics) -> bool {
+    let direction = dir(a, b, c);
+    test_struct.increment_turn(direction);
+    direction >= 0
+}
+
+/// Gift wrapping in place.
+/// Each hull point found is swapped to the end of the hull prefix, so the next wrapping step
+/// only scans the points after it, plus the first hull point to close the hull.
+/// Of collinear candidates the farthest is taken, so the hull has no collinear points.
+pub fn gift_ch_in_place(points: &mut [Point], test_struct: &mut Metrics) -> usize {
+    test_struct.set_start_time();
+    let n = points.len();
+    let min_index = match (0..n).min_by_key(|&i| points[i]) {
+        Some(i) => i,
+        None => {
+            test_struct.set_end_time();
+            return 0;
+        }
+    };
+    points.swap(0, min_index);
+
+    let mut hull_len = 1;
+    loop {
+        let hull_point = points[hull_len - 1];
+        let mut candidate = 0;
+        for j in hull_len..n {
+            let point = points[j];
+            if point == hull_point {
+                continue;
+            }
+            let current = points[candidate];
+            if current == hull_point {
+                candidate = j;
+                continue;
+            }
+            let direction = dir(hull_point, current, point);
+            test_struct.increment_turn(direction);
+            if direction > 0 || (direction == 0 && distance_squared(hull_point, point) > distance_squared(hull_point, current)) {
+                candidate = j;
+            }
+        }
+        if candidate == 0 {
+            break;
+        }
+        points.swap(hull_len, candidate);
+        hull_len += 1;
+    }
+    test_struct.set_end_time();
+    hull_len
+}
+
+fn distance_squared(a: Point, b: Point) -> i64 {
+    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::in_place::{gift_ch_in_place, inc_ch_in_place};
+    use crate::metrics::Metrics;
+    use crate::{inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn in_place_hulls_match_inc_ch() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let count = rng.gen_range(3, 60);
+            let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, 20), rng.gen_range(0, 20))).collect();
+            let expected = inc_ch(&points, &mut Metrics::new());
+
+            let mut inc_points = points.clone();
+            let len = inc_ch_in_place(&mut inc_points, &mut Metrics::new());
+            assert_eq!(&inc_points[..len], &expected[..], "inc_ch_in_place of {:?}", points);
+
+            let mut gift_points = points.clone();
+            let len = gift_ch_in_place(&mut gift_points, &mut Metrics::new());
+            assert_eq!(&gift_points[..len], &expected[..], "gift_ch_in_place of {:?}", points);
+        }
+    }
+
+    #[test]
+    fn in_place_hulls_keep_every_point() {
+        let points = vec![
+            Point::new(2, 2), Point::new(0, 0), Point::new(4, 0), Point::new(4, 4),
+            Point::new(0, 4), Point::new(2, 0), Point::new(1, 3), Point::new(0, 0),
+        ];
+        for algorithm in [inc_ch_in_place, gift_ch_in_place].iter() {
+            let mut reordered = points.clone();
+            let len = algorithm(&mut reordered, &mut Metrics::new());
+            assert_eq!(&reordered[..len], &[Point::new(0, 0), Point::new(0, 4), Point::new(4, 4), Point::new(4, 0)]);
+            let mut sorted = reordered.clone();
+            sorted.sort();
+            let mut expected = points.clone();
+            expected.sort();
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        for algorithm in [inc_ch_in_place, gift_ch_in_place].iter() {
+            assert_eq!(algorithm(&mut [], &mut Metrics::new()), 0);
+            assert_eq!(algorithm(&mut [Point::new(1, 1)], &mut Metrics::new()), 1);
+            assert_eq!(algorithm(&mut [Point::new(1, 1), Point::new(1, 1)], &mut Metrics::new()), 1);
+            let mut line = [Point::new(3, 3), Point::new(1, 1), Point::new(2, 2), Point::new(0, 0)];
+            let len = algorithm(&mut line, &mut Metrics::new());
+            assert_eq!(&line[..len], &[Point::new(0, 0), Point::new(3, 3)]);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Gift wrapping over a kd-tree, so a wrapping step doesn't scan every point.
+//! Every node of the tree knows the bounding box of its points. The points that would replace the
+//! current candidate are left of the line from the hull point through it, and a box with all four
+//! corners right of that line can't hold any of them, so its subtree is skipped. Subtrees whose
+//! boxes reach farthest left are searched first, which finds good candidates early and skips more.
+
+use std::time::Instant;
+use crate::metrics::Metrics;
+use crate::{dir, Point};
+
+/// Nodes with at most this many points are leaves, whose points are tested one by one.
+const LEAF_SIZE: usize = 16;
+
+struct Node {
+    min: Point,
+    max: Point,
+    start: usize,
+    end: usize,
+    children: Option<(usize, usize)>,
+    parent: usize,
+    /// Points under this node that haven't been removed.
+    alive: usize,
+}
+
+/// A kd-tree over a copy of the points, split at the median x and y in turns.
+/// Points are addressed by their index in the tree, and can be removed, which shrinks the boxes.
+pub(crate) struct KdTree {
+    points: Vec<Point>,
+    alive: Vec<bool>,
+    leaf_of: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl KdTree {
+    pub(crate) fn new(points: &[Point]) -> Self {
+        let mut tree = KdTree {
+            points: points.to_vec(),
+            alive: vec![true; points.len()],
+            leaf_of: vec![0; points.len()],
+            nodes: Vec::with_capacity(2 * points.len() / LEAF_SIZE + 1),
+        };
+        if !points.is_empty() {
+            tree.build(0, points.len(), true, 0);
+        }
+        tree
+    }
+
+    /// The points in tree order, which `point` and `remove` index into.
+    pub(crate) fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub(crate) fn is_alive(&self, index: usize) -> bool {
+        self.alive[index]
+    }
+
+    /// Builds the subtree over `start..end`, splitting at the median x or y by turns.
+    /// Boxes are joined from the children's, so only leaves look at their points.
+    fn build(&mut self, start: usize, end: usize, split_x: bool, parent: usize) -> usize {
+        let node = self.nodes.len();
+        let (min, max) = (Point::new(i64::MAX, i64::MAX), Point::new(i64::MIN, i64::MIN));
+        self.nodes.push(Node { min, max, start, end, children: None, parent, alive: end - start });
+        if end - start > LEAF_SIZE {
+            let middle = (end - start) / 2;
+            if split_x {
+                self.points[start..end].select_nth_unstable_by_key(middle, |p| p.x);
+            } else {
+                self.points[start..end].select_nth_unstable_by_key(middle, |p| p.y);
+            }
+            let left = self.build(start, start + middle, !split_x, node);
+            let right = self.build(start + middle, end, !split_x, node);
+            self.nodes[node].children = Some((left, right));
+        } else {
+            self.leaf_of[start..end].iter_mut().for_each(|leaf| *leaf = node);
+        }
+        self.fit_box(node);
+        node
+    }
+
+    /// Shrinks the box of `node` to its remaining points, or its children's boxes.
+    fn fit_box(&mut self, node: usize) {
+        let empty = (Point::new(i64::MAX, i64::MAX), Point::new(i64::MIN, i64::MIN));
+        let join = |(min, max): (Point, Point), (other_min, other_max): (Point, Point)| {
+            (Point::new(i64::min(min.x, other_min.x), i64::min(min.y, other_min.y)),
+             Point::new(i64::max(max.x, other_max.x), i64::max(max.y, other_max.y)))
+        };
+        let (min, max) = match self.nodes[node].children {
+            Some((left, right)) => [left, right]
+                .iter()
+                .filter(|&&child| self.nodes[child].alive > 0)
+                .fold(empty, |bounds, &child| join(bounds, (self.nodes[child].min, self.nodes[child].max))),
+            None => (self.nodes[node].start..self.nodes[node].end)
+                .filter(|&i| self.alive[i])
+                .fold(empty, |bounds, i| join(bounds, (self.points[i], self.points[i]))),
+        };
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+    }
+
+    /// Removes the point at `index` from every later search.
+    pub(crate) fn remove(&mut self, index: usize) {
+        if !self.alive[index] {
+            return;
+        }
+        self.alive[index] = false;
+        let mut node = self.leaf_of[index];
+        loop {
+            self.nodes[node].alive -= 1;
+            self.fit_box(node);
+            if node == 0 {
+                break;
+            }
+            node = self.nodes[node].parent;
+        }
+    }
+
+    /// How far left of the line from `from` through `candidate` the box of `node` reaches, as the
+    /// largest `dir` of its corners. Negative means every point in it is right of the line.
+    /// Every corner is a counted turn, and empty boxes take none.
+    fn reach(&self, node: usize, from: Point, candidate: Point, test_struct: &mut Metrics) -> i64 {
+        let Node { min, max, alive, .. } = self.nodes[node];
+        if alive == 0 {
+            return -1;
+        }
+        [min, Point::new(min.x, max.y), max, Point::new(max.x, min.y)]
+            .iter()
+            .map(|&corner| {
+                let direction = dir(from, candidate, corner);
+                test_struct.increment_turn(direction);
+                direction
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// The index of the next hull point of the remaining points after `from` going clockwise,
+    /// starting the search from the point at `candidate`.
+    /// Of collinear candidates the farthest is taken, like `gift_ch_in_place` does.
+    pub(crate) fn wrap_step(&self, from: Point, mut candidate: usize, search: &mut Search, test_struct: &mut Metrics) -> usize {
+        let mut stack = vec![(0, self.reach(0, from, self.points[candidate], test_struct), candidate)];
+        search.boxes += 1;
+        while let Some((node, reach, reached_from)) = stack.pop() {
+            let reach = if reached_from == candidate {
+                reach
+            } else {
+                search.boxes += 1;
+                self.reach(node, from, self.points[candidate], test_struct)
+            };
+            if reach < 0 {
+                search.pruned += 1;
+                continue;
+            }
+            search.visited += 1;
+            match self.nodes[node].children {
+                Some((left, right)) => {
+                    search.boxes += 2;
+                    let left_reach = self.reach(left, from, self.points[candidate], test_struct);
+                    let right_reach = self.reach(right, from, self.points[candidate], test_struct);
+                    if left_reach > right_reach {
+                        stack.push((right, right_reach, candidate));
+                        stack.push((left, left_reach, candidate));
+                    } else {
+                        stack.push((left, left_reach, candidate));
+                        stack.push((right, right_reach, candidate));
+                    }
+                }
+                None => {
+                    let node = &self.nodes[node];
+                    for i in node.start..node.end {
+                        let point = self.points[i];
+                        if !self.alive[i] || point == from {
+                            continue;
+                        }
+                        let current = self.points[candidate];
+                        let direction = dir(from, current, point);
+                        test_struct.increment_turn(direction);
+                        if direction > 0 || (direction == 0 && distance_squared(from, point) > distance_squared(from, current)) {
+                            candidate = i;
+                        }
+                    }
+                }
+            }
+        }
+        candidate
+    }
+}
+
+/// Work done by the wrapping steps, added to the metrics at the end rather than for every node.
+#[derive(Default)]
+pub(crate) struct Search {
+    boxes: i64,
+    visited: i64,
+    pruned: i64,
+}
+
+impl Search {
+    pub(crate) fn count(&self, test_struct: &mut Metrics) {
+        test_struct.count("boxes", self.boxes);
+        test_struct.count("nodes_visited", self.visited);
+        test_struct.count("nodes_pruned", self.pruned);
+    }
+}
+
+fn distance_squared(a: Point, b: Point) -> i64 {
+    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+}
+
+/// Gift wrapping with each step searching a kd-tree instead of scanning all points, in the same
+/// order as `inc_ch` returns hulls. Building the tree takes O(n log n), after which a step only
+/// tests the points of leaves whose boxes reach across the line to the best candidate so far.
+/// The turns include the corners of every box the search tests, and the counters `boxes`,
+/// `nodes_visited` and `nodes_pruned` count the search.
+pub fn gift_ch_indexed(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    test_struct.set_start_time();
+    let start = match points.iter().min() {
+        Some(&start) => start,
+        None => {
+            test_struct.set_end_time();
+            return Vec::new();
+        }
+    };
+
+    let phase = Instant::now();
+    let tree = KdTree::new(points);
+    test_struct.end_phase("build", phase);
+    let first_candidate = match tree.points().iter().position(|&p| p != start) {
+        Some(i) => i,
+        None => {
+            test_struct.set_end_time();
+            return vec![start];
+        }
+    };
+    let start_index = tree.points().iter().position(|&p| p == start).unwrap();
+
+    let phase = Instant::now();
+    let mut search = Search::default();
+    let mut hull = vec![start];
+    let mut from = tree.points()[tree.wrap_step(start, first_candidate, &mut search, test_struct)];
+    while from != start {
+        hull.push(from);
+        from = tree.points()[tree.wrap_step(from, start_index, &mut search, test_struct)];
+    }
+    test_struct.end_phase("wrap", phase);
+
+    search.count(test_struct);
+    test_struct.set_end_time();
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::indexed::gift_ch_indexed;
+    use crate::metrics::Metrics;
+    use crate::{gift_ch, inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn indexed_gift_wrapping_matches_inc_ch() {
+        let mut rng = rand::thread_rng();
+        // Small coordinate ranges make duplicates and collinear hull points.
+        for &(count, range) in [(3, 3), (20, 5), (100, 10), (1_000, 50), (20_000, 1_000_000)].iter() {
+            for _ in 0..20 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let expected = inc_ch(&points, &mut Metrics::new());
+                if expected.len() < 3 {
+                    continue;
+                }
+                assert_eq!(gift_ch_indexed(&points, &mut Metrics::new()), expected, "points {:?}", points);
+            }
+        }
+        assert!(gift_ch_indexed(&[], &mut Metrics::new()).is_empty());
+        assert_eq!(gift_ch_indexed(&[Point::new(1, 2); 3], &mut Metrics::new()), vec![Point::new(1, 2)]);
+        let line: Vec<Point> = (0..100).map(|i| Point::new(i, 3 * i)).collect();
+        assert_eq!(gift_ch_indexed(&line, &mut Metrics::new()), vec![Point::new(0, 0), Point::new(99, 297)]);
+    }
+
+    #[test]
+    fn sparse_hulls_test_few_points() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..100_000).map(|_| Point::new(rng.gen_range(0, 10_000), rng.gen_range(0, 10_000))).collect();
+        let mut scanned = Metrics::new();
+        let hull = gift_ch(&points, &mut scanned);
+        let mut indexed = Metrics::new();
+        gift_ch_indexed(&points, &mut indexed);
+        // Nothing is removed, so every box tested has points and its four corners are turns.
+        assert!(indexed.total_turns() >= 4 * indexed.counter("boxes"));
+        assert!(indexed.total_turns() * 50 < scanned.total_turns(), "{} turns for a hull of {}", indexed.total_turns(), hull.len());
+    }
+}
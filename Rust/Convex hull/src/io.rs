@@ -0,0 +1,89 @@
+//! Reading and writing points as text, one point per line.
+//! A line holds the x and y coordinate separated by whitespace or a comma, the format
+//! `Point::print` writes. Empty lines and lines starting with `#` are skipped.
+
+use std::io::{self, BufRead, Write};
+use crate::Point;
+
+/// Parses one line, returning `None` for lines without a point.
+pub fn parse_point(line: &str) -> Result<Option<Point>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut coordinates = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|c| !c.is_empty());
+    let mut coordinate = |name: &str| -> Result<i64, String> {
+        let value = coordinates.next().ok_or_else(|| format!("missing {} coordinate", name))?;
+        value.parse::<i64>().map_err(|e| format!("{}: {}", value, e))
+    };
+    let point = Point::new(coordinate("x")?, coordinate("y")?);
+    if coordinates.next().is_some() {
+        return Err(String::from("more than two coordinates"));
+    }
+    Ok(Some(point))
+}
+
+/// The points of a text stream, read lazily into a reused line buffer. Malformed lines are
+/// `InvalidData` errors that name the line.
+pub fn read_points<R: BufRead>(reader: R) -> Points<R> {
+    Points { reader, line: String::new(), line_number: 0 }
+}
+
+/// The iterator of `read_points`.
+pub struct Points<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+}
+
+impl<R: BufRead> Iterator for Points<R> {
+    type Item = io::Result<Point>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(e) => return Some(Err(e)),
+            }
+            match parse_point(&self.line) {
+                Ok(Some(point)) => return Some(Ok(point)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", self.line_number, e)))),
+            }
+        }
+    }
+}
+
+pub fn write_points<W: Write>(mut writer: W, points: &[Point]) -> io::Result<()> {
+    for point in points {
+        writeln!(writer, "{} {}", point.x, point.y)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::{read_points, write_points};
+    use crate::Point;
+
+    #[test]
+    fn points_round_trip() {
+        let points = vec![Point::new(1, -2), Point::new(30, 4)];
+        let mut text = Vec::new();
+        write_points(&mut text, &points).unwrap();
+        let read: Vec<Point> = read_points(&text[..]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(read, points);
+    }
+
+    #[test]
+    fn reads_commas_comments_and_reports_bad_lines() {
+        let text = "# telemetry\n1,2\n\n  3\t4 \n5 x\n6\n";
+        let read: Vec<_> = read_points(text.as_bytes()).map(|p| p.map_err(|e| e.to_string())).collect();
+        assert_eq!(read[0], Ok(Point::new(1, 2)));
+        assert_eq!(read[1], Ok(Point::new(3, 4)));
+        assert!(read[2].as_ref().unwrap_err().starts_with("line 5: x:"));
+        assert_eq!(read[3], Err(String::from("line 6: missing y coordinate")));
+    }
+}
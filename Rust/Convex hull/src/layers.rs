@@ -0,0 +1,162 @@
+//! Convex layers, or onion peeling: the hull of the points is the first layer, the hull of the
+//! points left after removing it the second, and so on until no points are left.
+//! Recomputing the hull for every layer takes O(n) per layer even with the points sorted once,
+//! and there can be O(n) layers. Here every layer is wrapped with the kd-tree of `indexed`, and
+//! its points are removed from the tree, so a layer costs a search per point on it instead.
+//! A search can still test every box of the tree, so the worst case is O(n²) like peeling, and
+//! the gain is in the boxes a search skips. `peel_layers` is the peeling it is measured against.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+use crate::indexed::{KdTree, Search};
+use crate::metrics::Metrics;
+use crate::{inc_ch, Point};
+
+/// A convex layer, in the same order as `inc_ch` returns hulls. The outermost layer has depth 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub depth: usize,
+    pub hull: Vec<Point>,
+}
+
+/// The convex layers of the points, from the outside in.
+/// Like repeatedly calling `inc_ch`, a layer only holds the corners of its hull: points on its
+/// edges are left for the layers inside it. Copies of a point are all removed with its layer.
+pub fn convex_layers(points: &[Point], test_struct: &mut Metrics) -> Vec<Layer> {
+    test_struct.set_start_time();
+    let phase = Instant::now();
+    let mut tree = KdTree::new(points);
+    // Every layer starts at its smallest point, which is the first remaining point in this order.
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_unstable_by_key(|&i| tree.points()[i]);
+    let mut position = vec![0; points.len()];
+    for (i, &index) in order.iter().enumerate() {
+        position[index] = i;
+    }
+    test_struct.end_phase("build", phase);
+
+    let phase = Instant::now();
+    let mut search = Search::default();
+    let mut layers = Vec::new();
+    let mut first = 0;
+    loop {
+        while first < order.len() && !tree.is_alive(order[first]) {
+            first += 1;
+        }
+        if first == order.len() {
+            break;
+        }
+        let start_index = order[first];
+        let start = tree.points()[start_index];
+        // The first step starts from the smallest point itself, which any other point replaces.
+        let mut hull = vec![start_index];
+        loop {
+            let from = tree.points()[hull[hull.len() - 1]];
+            let next = tree.wrap_step(from, start_index, &mut search, test_struct);
+            if tree.points()[next] == start {
+                break;
+            }
+            hull.push(next);
+        }
+
+        for &index in &hull {
+            let point = tree.points()[index];
+            let copies = order[..position[index]].iter().rev().take_while(|&&i| tree.points()[i] == point).count();
+            for &copy in &order[position[index] - copies..] {
+                if tree.points()[copy] != point {
+                    break;
+                }
+                tree.remove(copy);
+            }
+        }
+        layers.push(Layer {
+            depth: layers.len(),
+            hull: hull.into_iter().map(|i| tree.points()[i]).collect(),
+        });
+    }
+    test_struct.end_phase("peel", phase);
+
+    search.count(test_struct);
+    test_struct.count("layers", layers.len() as i64);
+    test_struct.set_end_time();
+    layers
+}
+
+/// The convex layers by computing the hull of the remaining points with `inc_ch` for every layer,
+/// which takes O(n log n) per layer.
+pub fn peel_layers(points: &[Point], test_struct: &mut Metrics) -> Vec<Layer> {
+    test_struct.set_start_time();
+    let mut remaining = points.to_vec();
+    let mut layers = Vec::new();
+    while !remaining.is_empty() {
+        // `inc_ch` times itself, so it counts into metrics of its own.
+        let mut layer_data = Metrics::new();
+        let mut hull = inc_ch(&remaining, &mut layer_data);
+        test_struct.merge_counters(&layer_data);
+        // inc_ch returns hulls of one or two points in hash order, and repeats equal points.
+        if hull.len() <= 2 {
+            hull.sort();
+            hull.dedup();
+        }
+        let on_hull: HashSet<Point> = hull.iter().copied().collect();
+        remaining.retain(|p| !on_hull.contains(p));
+        layers.push(Layer { depth: layers.len(), hull });
+    }
+    test_struct.count("layers", layers.len() as i64);
+    test_struct.set_end_time();
+    layers
+}
+
+/// The depth of the layer every point is on, in the order of `points`, as used to score
+/// outliers: the hull points have depth 0, and the deeper a point is, the more central it is.
+pub fn hull_depths(points: &[Point], test_struct: &mut Metrics) -> Vec<usize> {
+    let depths: HashMap<Point, usize> = convex_layers(points, test_struct)
+        .into_iter()
+        .flat_map(|layer| {
+            let depth = layer.depth;
+            layer.hull.into_iter().map(move |p| (p, depth))
+        })
+        .collect();
+    points.iter().map(|p| depths[p]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layers::{convex_layers, hull_depths, peel_layers};
+    use crate::metrics::Metrics;
+    use crate::Point;
+    use rand::Rng;
+
+    #[test]
+    fn layers_match_repeated_hulls() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(1, 3), (5, 3), (30, 5), (200, 20), (2_000, 1_000_000)].iter() {
+            for _ in 0..20 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let layers = convex_layers(&points, &mut Metrics::new());
+                for (depth, layer) in layers.iter().enumerate() {
+                    assert_eq!(layer.depth, depth);
+                }
+                assert_eq!(layers, peel_layers(&points, &mut Metrics::new()), "points {:?}", points);
+            }
+        }
+        assert!(convex_layers(&[], &mut Metrics::new()).is_empty());
+        assert!(peel_layers(&[], &mut Metrics::new()).is_empty());
+    }
+
+    #[test]
+    fn depths_of_nested_squares() {
+        let mut points = Vec::new();
+        for depth in 0..5 {
+            let (low, high) = (depth, 10 - depth);
+            points.extend_from_slice(&[Point::new(low, low), Point::new(low, high), Point::new(high, high), Point::new(high, low)]);
+        }
+        points.push(Point::new(5, 5));
+        points.push(Point::new(0, 0));
+        let mut metrics = Metrics::new();
+        let depths = hull_depths(&points, &mut metrics);
+        assert_eq!(&depths[..4], &[0, 0, 0, 0]);
+        assert_eq!(&depths[16..], &[4, 4, 4, 4, 5, 0]);
+        assert_eq!(metrics.counter("layers"), 6);
+    }
+}
@@ -0,0 +1,963 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::process::exit;
+use std::time::Instant;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use crate::hull::Hull;
+use crate::metrics::Metrics;
+use crate::query::HullIndex;
+
+pub mod approx;
+pub mod circle;
+pub mod dynamic;
+pub mod external;
+pub mod hull;
+pub mod in_place;
+pub mod indexed;
+pub mod io;
+pub mod layers;
+pub mod melkman;
+pub mod metrics;
+pub mod online;
+pub mod operations;
+pub mod parallel;
+pub mod query;
+pub mod simd;
+pub mod svg;
+
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Hash)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub fn new(x: i64, y: i64) -> Self {
+        Point {
+            x,
+            y,
+        }
+    }
+
+    pub fn print(self) {
+        println!("{} {}", self.x, self.y);
+    }
+
+    pub fn get_point(self) -> (f32, f32) {
+        (self.x as f32, self.y as f32)
+    }
+}
+
+/// These methods calculate at which side of the line point c is
+/// The line is created between a and b.
+/// There are 3 different outcomes
+/// < 0  -> left turn
+/// == 0 -> on the line
+/// > 0  -> right turn
+pub fn dir(a: Point, b: Point, c: Point) -> i64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+pub fn inc_ch(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    test_struct.set_start_time();
+    if points.len() <= 2 {
+        let set: HashSet<Point> = points.iter().copied().collect();
+        test_struct.set_end_time();
+        return Vec::from_iter(set.iter().copied());
+    }
+
+    let phase = Instant::now();
+    let mut cloned_points = points.to_vec();
+    cloned_points.sort();
+    let mut asc_sorted = cloned_points.clone();
+    cloned_points.reverse();
+    let mut desc_sorted = cloned_points;
+    test_struct.end_phase("sort", phase);
+    let mut lh: Vec<Point> = asc_sorted.drain(0..2).collect();
+    let mut uh: Vec<Point> = desc_sorted.drain(0..2).collect();
+
+    // Going clockwise from the leftmost point, the ascending pass builds the upper chain and the
+    // descending pass the lower one.
+    let phase = Instant::now();
+    generate_upper_hull(test_struct, &mut asc_sorted, &mut lh);
+    test_struct.end_phase("upper hull", phase);
+    let phase = Instant::now();
+    generate_upper_hull(test_struct, &mut desc_sorted, &mut uh);
+    test_struct.end_phase("lower hull", phase);
+
+    uh.pop();
+    uh.drain(0..1);
+    lh.append(&mut uh);
+    test_struct.set_end_time();
+    lh
+}
+
+fn generate_upper_hull(test_struct: &mut Metrics, desc_sorted: &mut Vec<Point>, uh: &mut Vec<Point>) {
+    for cur_point in desc_sorted {
+        loop {
+            if uh.len() < 2 {
+                break;
+            }
+            let direction = dir(uh[uh.len() - 2], uh[uh.len() - 1], *cur_point);
+            if direction < 0 {
+                break;
+            }
+            test_struct.increment_turn(direction);
+            uh.pop();
+        }
+
+        uh.push(*cur_point);
+        let d = dir(uh[uh.len() - 2], uh[uh.len() - 1], *cur_point);
+        test_struct.increment_turn(d);
+    }
+}
+
+pub fn gift_ch(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    test_struct.set_start_time();
+    let phase = Instant::now();
+    let hull = gift_wrap(points, test_struct);
+    test_struct.end_phase("wrap", phase);
+    test_struct.set_end_time();
+    hull
+}
+
+/// The wrapping loop of `gift_ch`, without timing, so Chan's algorithm can wrap its groups.
+fn gift_wrap(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    let mut hull = Vec::<Point>::new();
+    let mut hull_point: Point = match points.iter().min() {
+        Some(p) => *p,
+        None => return Vec::new(),
+    };
+    loop {
+        hull.push(hull_point);
+        let mut current_candidate: Point = match points.first() {
+            Some(p) => *p,
+            None => exit(8),
+        };
+
+        for &j in points {
+            let direction = dir(hull[hull.len() - 1], current_candidate, j);
+            test_struct.increment_turn(direction);
+            if current_candidate == hull_point || direction > 0 {
+                current_candidate = j;
+            }
+        }
+        hull_point = current_candidate;
+        if current_candidate == hull[0] {
+            break;
+        }
+    }
+    hull
+}
+
+/// Chan's algorithm: the points are split into groups, each group gets a hull, and gift wrapping
+/// over the groups takes the tangent from the current hull point to each of them in O(log m).
+/// It returns the hull in the same order as `inc_ch`.
+pub fn ch_ch(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    chan(points, test_struct, false)
+}
+
+/// Chan's algorithm with the subhulls found by the vectorised gift wrapping of `simd`. The wrap
+/// over the subhulls takes tangents like `ch_ch` does. It returns the hull in the same order as
+/// `inc_ch`.
+pub fn ch_ch_simd(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    chan(points, test_struct, true)
+}
+
+fn chan(points: &[Point], test_struct: &mut Metrics, simd: bool) -> Vec<Point> {
+    test_struct.set_start_time();
+    // Wrapping needs distinct leftmost and rightmost points to wrap between.
+    match (points.iter().min(), points.iter().max()) {
+        (Some(min), Some(max)) if min != max => {}
+        (min, _) => {
+            test_struct.set_end_time();
+            return min.into_iter().copied().collect();
+        }
+    }
+    for i in 1..(log_2(log_2(points.len() as i32) as i32) + 2) {
+        let (hull, success) = uh_with_size(points, i64::pow(2, i64::pow(2, i) as u32), test_struct, simd);
+        if success {
+            test_struct.set_end_time();
+            return hull;
+        }
+    }
+    test_struct.set_end_time();
+    Vec::<Point>::new()
+}
+
+fn uh_with_size(points: &[Point], h: i64, test_struct: &mut Metrics, simd: bool) -> (Vec<Point>, bool) {
+    let mut cloned_points = points.to_vec();
+    let mut subhulls = Vec::<HullIndex>::new();
+    let mut upper_hull = Vec::<Point>::new();
+    let mut lower_hull = Vec::<Point>::new();
+
+    let mut m = points.len() / (h as usize);
+    if m == 0 {
+        m = 1
+    }
+    let phase = Instant::now();
+    loop {
+        let min = usize::min(cloned_points.len(), m);
+        if min == 0 {
+            break;
+        }
+        let ps: Vec<Point> = cloned_points.drain(0..min).collect();
+        // Both keep the far end of collinear points, which tangents need.
+        let subhull = if simd {
+            simd::gift_wrap_soa(&simd::Points::new(&ps), test_struct)
+        } else {
+            // `inc_ch` times itself, so it counts into metrics of its own.
+            let mut group = Metrics::new();
+            let subhull = inc_ch(&ps, &mut group);
+            test_struct.merge_counters(&group);
+            subhull
+        };
+        subhulls.push(HullIndex::new(&Hull::new(subhull)));
+    }
+    test_struct.end_phase("Chan subhulls", phase);
+
+
+    let min_point = match points.iter().min() {
+        Some(point) => *point,
+        None => exit(7),
+    };
+    let max_point = match points.iter().max() {
+        Some(point) => *point,
+        None => exit(7),
+    };
+    let mut upper_point: Point = min_point;
+    let mut lower_point: Point = max_point;
+    let phase = Instant::now();
+
+    for _ in 0..h {
+        if upper_hull.len() < 2 || upper_hull[upper_hull.len() - 1] != max_point {
+            upper_hull.push(upper_point);
+        }
+        if lower_hull.len() < 2 || lower_hull[lower_hull.len() - 1] != min_point {
+            lower_hull.push(lower_point);
+        }
+
+        if (upper_hull.len() > 2 || lower_hull.len() > 2)
+            && upper_hull[upper_hull.len() - 1] == max_point
+            && lower_hull[lower_hull.len() - 1] == min_point {
+            break;
+        }
+
+        upper_point = tangent_step(&subhulls, upper_hull[upper_hull.len() - 1], test_struct);
+        lower_point = tangent_step(&subhulls, lower_hull[lower_hull.len() - 1], test_struct);
+    }
+
+    test_struct.end_phase("wrap", phase);
+    let succ = upper_hull[upper_hull.len() - 1] == max_point
+        && lower_hull[lower_hull.len() - 1] == min_point;
+    lower_hull.pop();
+    lower_hull.drain(0..1);
+    upper_hull.append(&mut lower_hull);
+    (upper_hull, succ)
+}
+
+/// The hull point after `from` going clockwise, from the right tangents of `from` to the subhulls:
+/// the tangent with no other right of the line to it, and the farthest one if several are
+/// collinear. A tangent takes O(log m) turns, which are counted like comparing the tangents is.
+fn tangent_step(subhulls: &[HullIndex], from: Point, test_struct: &mut Metrics) -> Point {
+    let distance_squared = |p: Point| (p.x - from.x).pow(2) + (p.y - from.y).pow(2);
+    let mut candidate = from;
+    for subhull in subhulls {
+        let tangent = match subhull.tangents_from_counted(from, &mut |direction| test_struct.increment_turn(direction)) {
+            Some((_, right)) => right,
+            None => continue,
+        };
+        if candidate == from {
+            candidate = tangent;
+            continue;
+        }
+        let direction = dir(from, candidate, tangent);
+        test_struct.increment_turn(direction);
+        if direction > 0 || (direction == 0 && distance_squared(tangent) > distance_squared(candidate)) {
+            candidate = tangent;
+        }
+    }
+    test_struct.count("tangent_queries", subhulls.len() as i64);
+    candidate
+}
+
+const fn num_bits<T>() -> usize { std::mem::size_of::<T>() * 8 }
+
+fn log_2(x: i32) -> u32 {
+    assert!(x > 0);
+    num_bits::<i32>() as u32 - x.leading_zeros() - 1
+}
+
+fn bridge(points: &[Point], a: i64, test_data: &mut Metrics, median: MedianSelection, rng: &mut StdRng) -> (Point, Point) {
+    test_data.count("bridges", 1);
+    if points.len() == 2 {
+        return if points[0].x < points[1].x {
+            (points[0], points[1])
+        } else {
+            (points[1], points[0])
+        };
+    }
+    let mut candidates = Vec::with_capacity(points.len());
+    let chunks = points.chunks_exact(2);
+    chunks.remainder().iter().for_each(|&p| candidates.push(p));
+    let pairs: Vec<_> = chunks
+        .map(|p| match p {
+            [p1, p2, ..] => if p1.x < p2.x { (*p1, *p2) } else { (*p2, *p1) },
+            _ => panic!("Chunks forgot how to chunk"),
+        })
+        .filter(|(p1, p2)|
+            if p1.x == p2.x {
+                if p1.y > p2.y {
+                    candidates.push(*p1);
+                } else {
+                    candidates.push(*p2);
+                }
+                false
+            } else { true }
+        )
+        .map(|(p1, p2)| (p1, p2, (p1.y as f64 - p2.y as f64) / (p1.x as f64 - p2.x as f64)))
+        .collect();
+
+    if pairs.is_empty() {
+        return bridge(&candidates, a, test_data, median, rng);
+    }
+
+    let slopes: Vec<_> = pairs.iter().map(|(_, _, s)| s).cloned().collect();
+    let slope_median = find_median(&slopes, median, rng);
+    // Comparing the slope of a pair with the median slope is the orientation test of the pair
+    // against the median direction, so it counts as a turn like a call to `dir` does.
+    for s in &slopes {
+        test_data.increment_turn(s.partial_cmp(&slope_median).unwrap() as i64);
+    }
+
+    let small: Vec<_> = pairs
+        .iter()
+        .filter(|(_, _, s)| *s < slope_median)
+        .map(|(p1, p2, _)| (*p1, *p2))
+        .collect();
+    let equal: Vec<_> = pairs
+        .iter()
+        .filter(|(_, _, s)| *s == slope_median)
+        .map(|(p1, p2, _)| (*p1, *p2))
+        .collect();
+    let large: Vec<_> = pairs
+        .iter()
+        .filter(|(_, _, s)| *s > slope_median)
+        .map(|(p1, p2, _)| (*p1, *p2))
+        .collect();
+
+    let funny_slopes: Vec<_> = points
+        .iter()
+        .map(|p| p.y as f64 - slope_median * p.x as f64)
+        .collect();
+    let max_slope = funny_slopes
+        .iter()
+        .max_by(|f1, f2| f1.partial_cmp(f2).unwrap())
+        .cloned()
+        .unwrap();
+
+    let max = {
+        let mut max = Vec::new();
+        for i in 0..points.len() {
+            // Likewise, this is the orientation test of the point against the supporting line.
+            test_data.increment_turn(funny_slopes[i].partial_cmp(&max_slope).unwrap() as i64);
+            if (funny_slopes[i] - max_slope).abs() < 10e-10 {
+                max.push(points[i]);
+            }
+        }
+        max
+    };
+
+    let pk = max
+        .iter()
+        .min_by_key(|p| p.x)
+        .cloned()
+        .unwrap();
+    let pm = max
+        .iter()
+        .max_by_key(|p| p.x)
+        .cloned()
+        .unwrap();
+    if pk.x <= a && pm.x > a {
+        return (pk, pm);
+    }
+
+    if pm.x <= a {
+        large.iter().cloned().for_each(|(_, p)| candidates.push(p));
+        equal.iter().cloned().for_each(|(_, p)| candidates.push(p));
+        small.iter().cloned().for_each(|(p1, p2)| {
+            candidates.push(p1);
+            candidates.push(p2);
+        });
+    }
+
+    if pk.x > a {
+        equal.iter().cloned().for_each(|(p, _)| candidates.push(p));
+        small.iter().cloned().for_each(|(p, _)| candidates.push(p));
+        large.iter().cloned().for_each(|(p1, p2)| {
+            candidates.push(p1);
+            candidates.push(p2);
+        });
+    }
+
+    let candidates: Vec<_> = candidates.into_iter().collect();
+    bridge(&candidates, a, test_data, median, rng)
+}
+
+/// How marriage-before-conquest chooses the medians it splits the points and the pairs by.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MedianSelection {
+    /// The median of this many randomly chosen items, or of all of them if there are fewer.
+    /// Medians of small samples can be far from the true median, which unbalances the recursion.
+    /// Samples need at least two items, since a single one can be the point with the largest x.
+    Sampled(usize),
+    /// The exact median, selected in linear time by median of medians. This keeps the worst case
+    /// at O(n log h) and makes the algorithm deterministic.
+    Exact,
+}
+
+/// The lower median of the x coordinates, or of a sample of them. A bridge crosses every x from
+/// the smallest up to but not including the largest, which the lower median of two or more x
+/// never is.
+fn find_median_x(points: &[Point], median: MedianSelection, rng: &mut StdRng) -> i64 {
+    let mut xs: Vec<i64> = match median {
+        MedianSelection::Sampled(sample_size) if sample_size < points.len() => points
+            .choose_multiple(rng, usize::max(sample_size, 2))
+            .map(|p| p.x)
+            .collect(),
+        _ => points.iter().map(|p| p.x).collect(),
+    };
+    let middle = (xs.len() - 1) / 2;
+    select(&mut xs, middle)
+}
+
+fn find_median<T: PartialOrd + Copy>(items: &[T], median: MedianSelection, rng: &mut StdRng) -> T {
+    let mut sample: Vec<T> = match median {
+        MedianSelection::Sampled(sample_size) if sample_size < items.len() => items
+            .choose_multiple(rng, sample_size)
+            .cloned()
+            .collect(),
+        _ => items.to_vec(),
+    };
+    let middle = sample.len() / 2;
+    select(&mut sample, middle)
+}
+
+/// The `k`th smallest of `items`, which are reordered, by median of medians: the medians of
+/// groups of five are moved to the front, and the median of those is selected recursively and
+/// partitioned around. At least 30% of the items are on either side of it, so this is O(n).
+fn select<T: PartialOrd + Copy>(items: &mut [T], k: usize) -> T {
+    let by_value = |a: &T, b: &T| a.partial_cmp(b).unwrap();
+    if items.len() <= 5 {
+        items.sort_by(by_value);
+        return items[k];
+    }
+
+    let groups = items.len().div_ceil(5);
+    for group in 0..groups {
+        let start = group * 5;
+        let end = usize::min(start + 5, items.len());
+        items[start..end].sort_by(by_value);
+        items.swap(group, start + (end - start) / 2);
+    }
+    let pivot = select(&mut items[..groups], groups / 2);
+
+    // Partition into the items smaller than, equal to and larger than the pivot.
+    let (mut smaller, mut equal, mut larger) = (0, 0, items.len());
+    while equal < larger {
+        match by_value(&items[equal], &pivot) {
+            Ordering::Less => {
+                items.swap(smaller, equal);
+                smaller += 1;
+                equal += 1;
+            }
+            Ordering::Equal => equal += 1,
+            Ordering::Greater => {
+                larger -= 1;
+                items.swap(equal, larger);
+            }
+        }
+    }
+    if k < smaller {
+        select(&mut items[..smaller], k)
+    } else if k < larger {
+        pivot
+    } else {
+        select(&mut items[larger..], k - larger)
+    }
+}
+
+/// Kirkpatrick and Seidel's marriage-before-conquest, with medians of random samples of five.
+pub fn mbc_ch(points: Vec<Point>, test_data: &mut Metrics) -> Vec<Point> {
+    mbc_ch_with(points, test_data, MedianSelection::Sampled(5), rand::random())
+}
+
+/// Marriage-before-conquest with the medians chosen by `median`. Sampled medians are drawn from
+/// a generator seeded with `seed`, so the same seed repeats the same recursion and counts.
+pub fn mbc_ch_with(points: Vec<Point>, test_data: &mut Metrics, median: MedianSelection, seed: u64) -> Vec<Point> {
+    test_data.set_start_time();
+    // The lower hull below drops the ends shared with the upper hull, which needs two of them.
+    if points.len() < 2 {
+        test_data.set_end_time();
+        return points;
+    }
+    let lh_points: Vec<_> = points
+        .iter()
+        .map(|p| Point { x: p.x, y: -p.y })
+        .collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let lh = mbc_ch_inner(lh_points, test_data, median, &mut rng);
+    let mut real_lh: Vec<_> = lh
+        .iter()
+        .skip(1)
+        .take(lh.len() - 2)
+        .rev()
+        .map(|p| Point { x: p.x, y: -p.y })
+        .collect();
+
+    let mut hull = mbc_ch_inner(points, test_data, median, &mut rng);
+    hull.append(&mut real_lh);
+    test_data.set_end_time();
+    hull
+}
+
+fn mbc_ch_inner(points: Vec<Point>, test_data: &mut Metrics, median: MedianSelection, rng: &mut StdRng) -> Vec<Point> {
+    test_data.count("recursions", 1);
+    if points.len() <= 2 {
+        return mbc_base_case(points);
+    }
+
+    let (left, right) = mbc_split(points, test_data, median, rng);
+    let mut hull = mbc_ch_inner(left, test_data, median, rng);
+    hull.append(&mut mbc_ch_inner(right, test_data, median, rng));
+    hull
+}
+
+/// The upper hull of at most two points, from left to right.
+fn mbc_base_case(points: Vec<Point>) -> Vec<Point> {
+    if points.len() == 2 && points[0].x >= points[1].x {
+        vec![points[1], points[0]]
+    } else {
+        points
+    }
+}
+
+/// Finds the bridge over the median and splits the points into the subproblems left and right
+/// of it, each ending or starting at its end of the bridge.
+fn mbc_split(points: Vec<Point>, test_data: &mut Metrics, median: MedianSelection, rng: &mut StdRng) -> (Vec<Point>, Vec<Point>) {
+    let phase = Instant::now();
+    let median_x = find_median_x(&points, median, rng);
+    test_data.end_phase("median", phase);
+
+    let phase = Instant::now();
+    let (left_point, right_point) = bridge(&points, median_x, test_data, median, rng);
+    test_data.end_phase("bridge", phase);
+
+    let phase = Instant::now();
+    let left = {
+        let mut left: Vec<_> = points
+            .iter()
+            .filter(|p| p.x < left_point.x)
+            .cloned()
+            .collect();
+        left.push(left_point);
+        left
+    };
+    let right = {
+        let mut right: Vec<Point> = points
+            .into_iter()
+            .filter(|p| p.x > right_point.x)
+            .collect();
+        right.push(right_point);
+        right
+    };
+    test_data.end_phase("partition", phase);
+    (left, right)
+}
+
+/// Marriage-before-conquest with the upper and lower hulls, and every pair of subproblems with
+/// at least `cutoff` points, run as rayon tasks. Smaller subproblems recurse sequentially.
+/// Tasks count into their own metrics, whose counters are added to `test_data` when they join,
+/// so `recursions` and `bridges` match the sequential algorithm. Phases are only timed on the
+/// calling thread, since the time of other threads overlaps with it. Every task samples from its
+/// own generator, seeded from its parent's, so a seed repeats the same counts however the tasks
+/// are scheduled.
+pub fn mbc_ch_par(points: Vec<Point>, test_data: &mut Metrics, cutoff: usize, median: MedianSelection, seed: u64) -> Vec<Point> {
+    test_data.set_start_time();
+    if points.len() < 2 {
+        test_data.set_end_time();
+        return points;
+    }
+    let lh_points: Vec<_> = points
+        .iter()
+        .map(|p| Point { x: p.x, y: -p.y })
+        .collect();
+    let mut lh_data = Metrics::new();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut lh_rng = StdRng::seed_from_u64(rng.gen());
+    let (mut hull, lh) = rayon::join(
+        || mbc_ch_par_inner(points, test_data, cutoff, median, &mut rng),
+        || mbc_ch_par_inner(lh_points, &mut lh_data, cutoff, median, &mut lh_rng),
+    );
+    test_data.merge_counters(&lh_data);
+    hull.extend(lh
+        .iter()
+        .skip(1)
+        .take(lh.len() - 2)
+        .rev()
+        .map(|p| Point { x: p.x, y: -p.y }));
+    test_data.set_end_time();
+    hull
+}
+
+fn mbc_ch_par_inner(points: Vec<Point>, test_data: &mut Metrics, cutoff: usize, median: MedianSelection, rng: &mut StdRng) -> Vec<Point> {
+    if points.len() <= 2 || points.len() < cutoff {
+        return mbc_ch_inner(points, test_data, median, rng);
+    }
+    test_data.count("recursions", 1);
+
+    let (left, right) = mbc_split(points, test_data, median, rng);
+    let mut right_data = Metrics::new();
+    let mut right_rng = StdRng::seed_from_u64(rng.gen());
+    let (mut hull, mut right_hull) = rayon::join(
+        || mbc_ch_par_inner(left, test_data, cutoff, median, rng),
+        || mbc_ch_par_inner(right, &mut right_data, cutoff, median, &mut right_rng),
+    );
+    test_data.merge_counters(&right_data);
+    hull.append(&mut right_hull);
+    hull
+}
+
+/// Preparata and Hong's divide and conquer: the points are sorted once, split at the median x
+/// and the hulls of both halves merged along their upper and lower tangents. Unlike
+/// marriage-before-conquest, the subproblems are solved before the bridges are found.
+pub fn dc_ch(points: &[Point], test_data: &mut Metrics) -> Vec<Point> {
+    test_data.set_start_time();
+    let phase = Instant::now();
+    let mut sorted = points.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    test_data.end_phase("sort", phase);
+
+    let phase = Instant::now();
+    let hull = if sorted.is_empty() { sorted } else { dc_ch_inner(&sorted, test_data) };
+    test_data.end_phase("merge", phase);
+    test_data.set_end_time();
+    hull
+}
+
+/// The hull of sorted, distinct points, in the order of `inc_ch`.
+fn dc_ch_inner(sorted: &[Point], test_data: &mut Metrics) -> Vec<Point> {
+    test_data.count("recursions", 1);
+    if sorted.len() == 1 {
+        return sorted.to_vec();
+    }
+    let (left, right) = sorted.split_at(sorted.len() / 2);
+    let left = dc_ch_inner(left, test_data);
+    let right = dc_ch_inner(right, test_data);
+    dc_merge(&left, &right, test_data)
+}
+
+/// Merges the hulls of points left and right of a vertical line. Both hulls go clockwise from
+/// their smallest point, so the upper tangent is found by walking back from the rightmost point
+/// of `left` and forward from the leftmost point of `right`, and the lower tangent the other way.
+fn dc_merge(left: &[Point], right: &[Point], test_data: &mut Metrics) -> Vec<Point> {
+    test_data.count("merges", 1);
+    let next = |hull: &[Point], i: usize| (i + 1) % hull.len();
+    let prev = |hull: &[Point], i: usize| (i + hull.len() - 1) % hull.len();
+    // Whether `c` is on the outer side of the line from `a` to `b`, or on the line beyond `from`,
+    // where it replaces `from` so the merged hull has no collinear points.
+    let mut outside = |a: Point, b: Point, c: Point, from: Point, upper: bool| {
+        let direction = dir(a, b, c);
+        test_data.increment_turn(direction);
+        let to = if from == a { b } else { a };
+        let beyond = (c.x - from.x) * (to.x - from.x) + (c.y - from.y) * (to.y - from.y) < 0;
+        (if upper { direction > 0 } else { direction < 0 }) || (direction == 0 && beyond)
+    };
+
+    let rightmost = (0..left.len()).max_by_key(|&i| left[i]).unwrap();
+    let mut tangent = |upper: bool| {
+        let (mut i, mut j) = (rightmost, 0);
+        loop {
+            let moved_left = if upper { prev(left, i) } else { next(left, i) };
+            if outside(left[i], right[j], left[moved_left], left[i], upper) {
+                i = moved_left;
+                continue;
+            }
+            let moved_right = if upper { next(right, j) } else { prev(right, j) };
+            if outside(left[i], right[j], right[moved_right], right[j], upper) {
+                j = moved_right;
+                continue;
+            }
+            return (i, j);
+        }
+    };
+    let (upper_left, upper_right) = tangent(true);
+    let (lower_left, lower_right) = tangent(false);
+
+    let mut hull = left[..=upper_left].to_vec();
+    let mut j = upper_right;
+    hull.push(right[j]);
+    while j != lower_right {
+        j = next(right, j);
+        hull.push(right[j]);
+    }
+    if lower_left != 0 {
+        hull.extend_from_slice(&left[lower_left..]);
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, ch_ch, dc_ch, inc_ch, mbc_ch, mbc_ch_with, mbc_ch_par, bridge, select, MedianSelection, Metrics};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn mbc_ch_test() {
+        let points = vec![
+            Point { x: 41, y: -6 },
+            Point { x: -24, y: -74 },
+            Point { x: -51, y: -6 },
+            Point { x: 73, y: 17 },
+            Point { x: -30, y: -34 },
+        ];
+        let mut test_data = Metrics::new();
+        assert_eq!(mbc_ch(points, &mut test_data), [Point { x: -51, y: -6 }, Point { x: 73, y: 17 }, Point { x: -24, y: -74 }]);
+    }
+
+    #[test]
+    fn mbc_ch_test2() {
+        let points = vec![
+            Point { x: 4, y: 11 },
+            Point { x: 14, y: 13 },
+            Point { x: 21, y: 10 },
+            Point { x: 18, y: 11 },
+            Point { x: 10, y: 11 },
+            Point { x: 13, y: 6 },
+            Point { x: 7, y: 6 },
+            Point { x: 19, y: 5 },
+            Point { x: 1, y: 7 },
+        ];
+        let mut test_data = Metrics::new();
+        println!("{:?}", mbc_ch(points, &mut test_data));
+    }
+
+    #[test]
+    fn mbc_ch_of_fewer_than_two_points() {
+        for points in [vec![], vec![Point::new(3, -4)]].iter() {
+            assert_eq!(mbc_ch(points.clone(), &mut Metrics::new()), *points);
+            assert_eq!(mbc_ch_par(points.clone(), &mut Metrics::new(), 0, MedianSelection::Exact, 0), *points);
+        }
+    }
+
+    #[test]
+    fn mbc_ch_par_matches_mbc_ch() {
+        let mut rng = rand::thread_rng();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let points: Vec<Point> = (0..20_000).map(|_| Point::new(rng.gen_range(0, 1_000_000), rng.gen_range(0, 1_000_000))).collect();
+        let expected = mbc_ch(points.clone(), &mut Metrics::new());
+        for &cutoff in [0, 100, 1_000_000].iter() {
+            let median = MedianSelection::Sampled(5);
+            let seed = rng.gen();
+            assert_eq!(pool.install(|| mbc_ch_par(points.clone(), &mut Metrics::new(), cutoff, median, seed)), expected);
+        }
+
+        // Exact medians make the counts deterministic.
+        let mut sequential = Metrics::new();
+        mbc_ch_with(points.clone(), &mut sequential, MedianSelection::Exact, 0);
+        let mut parallel = Metrics::new();
+        pool.install(|| mbc_ch_par(points, &mut parallel, 100, MedianSelection::Exact, 0));
+        assert_eq!(parallel.counter("recursions"), sequential.counter("recursions"));
+        assert_eq!(parallel.counter("bridges"), sequential.counter("bridges"));
+        assert_eq!(parallel.total_turns(), sequential.total_turns());
+    }
+
+    #[test]
+    fn exact_medians_are_deterministic() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..20_000).map(|_| Point::new(rng.gen_range(0, 1_000_000), rng.gen_range(0, 1_000_000))).collect();
+        let expected = mbc_ch(points.clone(), &mut Metrics::new());
+        let mut first = Metrics::new();
+        assert_eq!(mbc_ch_with(points.clone(), &mut first, MedianSelection::Exact, 0), expected);
+        let mut second = Metrics::new();
+        assert_eq!(mbc_ch_with(points.clone(), &mut second, MedianSelection::Exact, 1), expected);
+        assert_eq!(first.counters(), second.counters());
+        // Samples larger than the input are the whole input, as are samples from fewer points.
+        for &size in [2, 4, 5, 6, 1_000, 100_000].iter() {
+            assert_eq!(mbc_ch_with(points[..7].to_vec(), &mut Metrics::new(), MedianSelection::Sampled(size), rng.gen()),
+                       mbc_ch(points[..7].to_vec(), &mut Metrics::new()));
+        }
+    }
+
+    #[test]
+    fn seeded_samples_are_deterministic() {
+        let mut rng = rand::thread_rng();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let points: Vec<Point> = (0..20_000).map(|_| Point::new(rng.gen_range(0, 1_000_000), rng.gen_range(0, 1_000_000))).collect();
+        let seed = rng.gen();
+        let (mut first, mut second) = (Metrics::new(), Metrics::new());
+        mbc_ch_with(points.clone(), &mut first, MedianSelection::Sampled(5), seed);
+        mbc_ch_with(points.clone(), &mut second, MedianSelection::Sampled(5), seed);
+        assert_eq!(first.counters(), second.counters());
+        let (mut first, mut second) = (Metrics::new(), Metrics::new());
+        pool.install(|| mbc_ch_par(points.clone(), &mut first, 100, MedianSelection::Sampled(5), seed));
+        pool.install(|| mbc_ch_par(points, &mut second, 100, MedianSelection::Sampled(5), seed));
+        assert_eq!(first.counters(), second.counters());
+    }
+
+    #[test]
+    fn select_finds_the_kth_smallest() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(1, 10), (7, 3), (100, 5), (1_000, 1_000_000)].iter() {
+            let items: Vec<i64> = (0..count).map(|_| rng.gen_range(0, range)).collect();
+            let mut sorted = items.clone();
+            sorted.sort();
+            for (k, &expected) in sorted.iter().enumerate() {
+                assert_eq!(select(&mut items.clone(), k), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn dc_ch_matches_inc_ch() {
+        let mut rng = rand::thread_rng();
+        // Small coordinate ranges make duplicates, shared x values and collinear hull points.
+        for &(count, range) in [(1, 10), (2, 2), (3, 3), (10, 3), (50, 5), (200, 20), (5_000, 1_000_000)].iter() {
+            for _ in 0..50 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let mut expected = inc_ch(&points, &mut Metrics::new());
+                // inc_ch returns hulls of one or two points in hash order, and repeats equal points.
+                if expected.len() <= 2 {
+                    expected.sort();
+                    expected.dedup();
+                }
+                assert_eq!(dc_ch(&points, &mut Metrics::new()), expected, "points {:?}", points);
+            }
+        }
+        let line: Vec<Point> = (0..10).map(|i| Point::new(i, 2 * i)).collect();
+        assert_eq!(dc_ch(&line, &mut Metrics::new()), vec![Point::new(0, 0), Point::new(9, 18)]);
+    }
+
+    #[test]
+    fn ch_ch_matches_inc_ch() {
+        let mut rng = rand::thread_rng();
+        // Small coordinate ranges make duplicates, two point hulls and collinear hull points.
+        for &(count, range) in [(1, 10), (2, 2), (3, 3), (10, 3), (50, 5), (200, 20), (5_000, 1_000), (20_000, 1_000_000)].iter() {
+            for _ in 0..50 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let mut expected = inc_ch(&points, &mut Metrics::new());
+                // inc_ch returns hulls of one or two points in hash order, and repeats equal points.
+                if expected.len() <= 2 {
+                    expected.sort();
+                    expected.dedup();
+                }
+                assert_eq!(ch_ch(&points, &mut Metrics::new()), expected, "points {:?}", points);
+            }
+        }
+        assert!(ch_ch(&[], &mut Metrics::new()).is_empty());
+        assert_eq!(ch_ch(&[Point::new(3, 1), Point::new(0, 0)], &mut Metrics::new()), vec![Point::new(0, 0), Point::new(3, 1)]);
+        for line in [(0..100).map(|i| Point::new(i, 3 * i)).collect::<Vec<_>>(), (0..100).map(|i| Point::new(7, 99 - i)).collect()].iter() {
+            assert_eq!(ch_ch(line, &mut Metrics::new()), vec![*line.iter().min().unwrap(), *line.iter().max().unwrap()]);
+        }
+    }
+
+    #[test]
+    fn bridge_test_two_points() {
+        let points = bridge(&[
+            Point { x: 4, y: 11 },
+            Point { x: 14, y: 13 },
+        ], 4, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 14, y: 13 }));
+        let points = bridge(&[
+            Point { x: 14, y: 13 },
+            Point { x: 4, y: 11 },
+        ], 4, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 14, y: 13 }));
+    }
+
+    #[test]
+    fn bridge_test_three_points() {
+        let points = bridge(&[
+            Point { x: 19, y: 5 },
+            Point { x: 21, y: 10 },
+            Point { x: 13, y: 6 },
+        ], 13, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 13, y: 6 }, Point { x: 21, y: 10 }));
+        let points = bridge(&[
+            Point { x: 21, y: 10 },
+            Point { x: 19, y: 5 },
+            Point { x: 13, y: 6 },
+        ], 13, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 13, y: 6 }, Point { x: 21, y: 10 }));
+        let points = bridge(&[
+            Point { x: 13, y: 6 },
+            Point { x: 19, y: 5 },
+            Point { x: 21, y: 10 },
+        ], 13, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 13, y: 6 }, Point { x: 21, y: 10 }));
+    }
+
+    #[test]
+    fn bridge_test1() {
+        let points = bridge(&[
+            Point { x: 4, y: 11 },
+            Point { x: 14, y: 13 },
+            Point { x: 21, y: 10 },
+            Point { x: 18, y: 11 },
+            Point { x: 10, y: 11 },
+            Point { x: 13, y: 6 },
+            Point { x: 7, y: 6 },
+            Point { x: 19, y: 5 },
+            Point { x: 1, y: 7 },
+        ], 10, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 14, y: 13 }));
+    }
+
+    #[test]
+    fn bridge_test2() {
+        let points = bridge(&[
+            Point { x: 4, y: 11 },
+            Point { x: 21, y: 10 },
+            Point { x: 18, y: 11 },
+            Point { x: 10, y: 11 },
+            Point { x: 13, y: 6 },
+            Point { x: 7, y: 6 },
+            Point { x: 19, y: 5 },
+            Point { x: 1, y: 7 },
+        ], 10, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0));
+        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 18, y: 11 }));
+    }
+
+    #[test]
+    fn that_damn_float_precision_bug() {
+        let points = vec![
+            Point { x: 804, y: 2271 }, Point { x: -136, y: 2382 }, Point { x: 2686, y: 4952 }, Point { x: -289, y: 3189 }, Point { x: 6560, y: 1644 }
+        ];
+        assert_eq!(bridge(&points, 804, &mut Metrics::new(), MedianSelection::Exact, &mut StdRng::seed_from_u64(0)), (Point { x: -289, y: 3189 }, Point { x: 2686, y: 4952 }));
+    }
+
+    #[test]
+    fn mbc_perf() {
+        let mut rng = rand::thread_rng();
+        let mut points = std::vec::Vec::<Point>::new();
+
+        for _ in 1..10000 {
+            let a = rng.gen_range(0f64, 360f64);
+            let r = 4000f64 * f64::sqrt(rng.gen_range(0f64, 1f64));
+            let xx = (r * f64::cos(a)) as i64 + 3000;
+            let y = (r * f64::sin(a)) as i64 + 3000;
+
+            points.push(Point::new(xx, y))
+        }
+        let mut test_data = Metrics::new();
+        mbc_ch(points, &mut test_data);
+        println!("{:?}", test_data.time_elapsed().as_nanos())
+    }
+}
\ No newline at end of file
@@ -1,126 +1,105 @@
 use rand::Rng;
 use std::collections::HashSet;
-use std::iter::FromIterator;
-use std::process::exit;
-use std::time::Duration;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::time::Instant;
 use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use clap::{App, Arg};
-
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Hash)]
-pub struct Point {
-    x: i64,
-    y: i64,
-}
-
-impl Point {
-    pub fn new(x: i64, y: i64) -> Self {
-        Point {
-            x,
-            y,
-        }
-    }
-
-    pub fn print(self) {
-        println!("{} {}", self.x, self.y);
-    }
-
-    pub fn get_point(self) -> (f32, f32) {
-        (self.x as f32, self.y as f32)
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
+use convexhull::{Point, MedianSelection, inc_ch, gift_ch, ch_ch, ch_ch_simd, dc_ch, mbc_ch_with, mbc_ch_par};
+use convexhull::in_place::{inc_ch_in_place, gift_ch_in_place};
+use convexhull::indexed::gift_ch_indexed;
+use convexhull::approx::{approx_ch, distance_outside};
+use convexhull::circle::{min_enclosing_circle, welzl, MAX_SPAN};
+use convexhull::dynamic::DynamicHull;
+use convexhull::external::external_ch;
+use convexhull::hull::Hull;
+use convexhull::io;
+use convexhull::layers::{convex_layers, peel_layers, Layer};
+use convexhull::melkman::{melkman_ch, random_simple_polygon};
+use convexhull::metrics::Metrics;
+use convexhull::online::OnlineHull;
+use convexhull::parallel::par_ch;
+use convexhull::query::{HullIndex, Location};
+use convexhull::simd::gift_ch_simd;
+use convexhull::svg;
+use crate::baseline::{Baseline, Dataset, Verdict};
+use crate::report::{Format, Report, Sample, Shape};
+use crate::stats::Summary;
+
+mod alloc;
+mod baseline;
+mod report;
+mod stats;
+mod sweep;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 enum Algorithm {
     Incremental,
+    IncrementalInPlace,
     Gift,
+    GiftInPlace,
+    GiftSimd,
+    GiftIndexed,
     Chan,
+    ChanSimd,
     MBC,
+    DivideAndConquer,
+    Parallel,
+    ParallelMBC,
+    Dynamic,
+    Batch,
+    Online,
+    Approximate,
+    Melkman,
+    Layers,
+    LayersPeel,
 }
 
-#[derive(Copy, Clone)]
-pub struct TestData {
-    right_turn: i64,
-    left_turn: i64,
-    no_turn: i64,
-    time_start: std::time::Duration,
-    time_end: std::time::Duration,
+/// What a report is of: an algorithm run on generated points, or the hull of a point file, which
+/// `run_file` reads again for every run.
+#[derive(Copy, Clone, PartialEq)]
+enum Method {
+    Algorithm(Algorithm),
+    External,
 }
 
-impl TestData {
-    fn new() -> Self {
-        TestData {
-            right_turn: 0,
-            left_turn: 0,
-            no_turn: 0,
-            time_start: Default::default(),
-            time_end: Default::default(),
+impl fmt::Debug for Method {
+    /// Prints the name of the algorithm, which reports and baselines are keyed by.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Algorithm(algorithm) => algorithm.fmt(f),
+            Method::External => f.write_str("External"),
         }
     }
-    fn increment_turn(&mut self, direction: i64) {
-        if direction == 0 { self.increment_no_turn() } else if direction > 0 { self.increment_right_turn() } else { self.increment_left_turn() }
-    }
-    fn increment_right_turn(&mut self) { self.right_turn += 1 }
-    fn increment_left_turn(&mut self) { self.left_turn += 1 }
-    fn increment_no_turn(&mut self) { self.no_turn += 1 }
-    fn set_start_time(&mut self) { self.time_start = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).expect("Time went backwards") }
-    fn set_end_time(&mut self) { self.time_end = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).expect("Time went backwards") }
-    fn time_elapsed(self) -> Duration { self.time_end - self.time_start }
-    fn print_data(&mut self, algorithm: Algorithm, input_size: usize, output_size: usize) {
-        println!("------- Method: {:?} statistics -------", algorithm);
-        println!("Input length: {}, Output length: {}", input_size, output_size);
-        println!("Ran in {} nanoseconds", self.time_elapsed().as_nanos());
-        println!("Right turns {}", self.right_turn);
-        println!("Left turns {}", self.left_turn);
-        println!("No turns {}", self.no_turn);
-        println!("Total turns {}", self.right_turn + self.left_turn + self.no_turn);
-        println!("-------------------------------------");
-        println!();
-    }
-    fn print_data_csv(&mut self, algorithm: Algorithm, input_size: usize, output_size: usize) {
-        println!("{:?},{},{},{},{},{},{},{}",
-                 algorithm,
-                 input_size, output_size,
-                 self.time_elapsed().as_nanos(),
-                 self.right_turn, self.left_turn, self.no_turn,
-                 self.right_turn + self.left_turn + self.no_turn
-        );
-    }
 }
 
-struct MBCTestData {
-    time_start: std::time::SystemTime,
-    time_end: std::time::SystemTime,
-    bridges: u64,
-    recursions: u64,
-}
+/// Parallel algorithms and the sequential algorithm their speedup is measured against.
+const SPEEDUPS: [(Algorithm, Algorithm); 2] = [
+    (Algorithm::Parallel, Algorithm::Incremental),
+    (Algorithm::ParallelMBC, Algorithm::MBC),
+];
 
-impl MBCTestData {
-    fn new() -> Self {
-        Self {
-            time_start: std::time::SystemTime::now(),
-            time_end: std::time::SystemTime::now(),
-            bridges: 0,
-            recursions: 0,
-        }
-    }
-    fn time_elapsed(&self) -> Duration { self.time_end.duration_since(self.time_start).unwrap() }
-    fn print_data(&mut self, input_size: usize, output_size: usize) {
-        println!("------- Method: {:?} statistics -------", Algorithm::MBC);
-        println!("Input length: {}, Output length: {}", input_size, output_size);
-        println!("Ran in {} nanoseconds", self.time_elapsed().as_nanos());
-        println!("Executed {} recursions", self.recursions);
-        println!("Executed bridges {} times", self.bridges);
-        println!("-------------------------------------");
-        println!();
-    }
-    fn print_data_csv(&mut self, input_size: usize, output_size: usize) {
-        println!("{:?},{},{},{},{},{}",
-                 Algorithm::MBC,
-                 input_size, output_size,
-                 self.time_elapsed().as_nanos(),
-                 self.recursions, self.bridges
-        );
-    }
+/// How every algorithm is run and reported.
+#[derive(Debug, Copy, Clone)]
+struct Settings {
+    sample_count: usize,
+    warmup_count: usize,
+    format: Format,
+    raw: bool,
+    /// Subproblems of parallel marriage-before-conquest smaller than this run sequentially.
+    mbc_cutoff: usize,
+    /// How marriage-before-conquest chooses its medians.
+    mbc_median: MedianSelection,
+    /// Seeds the sampled medians, so every run of a dataset makes the same counts.
+    seed: u64,
+    /// Points that arrive and leave the sliding window of the dynamic and batch algorithms.
+    updates: usize,
+    /// Directions the approximate hull samples extreme points in.
+    directions: usize,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -128,6 +107,7 @@ enum PointGeneratorStrategy {
     Square,
     Circle,
     Exp,
+    Polygon,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -143,12 +123,126 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .long("samples")
             .help("Amount of times to run algorithms on dataset")
             .default_value("1"))
+        .arg(Arg::with_name("warmup")
+            .short("w")
+            .long("warmup")
+            .help("Amount of uncounted runs before sampling each algorithm")
+            .default_value("3"))
+        .arg(Arg::with_name("sweep")
+            .long("sweep")
+            .value_name("FROM..TO")
+            .takes_value(true)
+            .help("Runs the algorithms on log-spaced point counts in the range and fits their complexity."))
+        .arg(Arg::with_name("steps")
+            .long("steps")
+            .help("Amount of point counts in a sweep")
+            .default_value("10"))
+        .arg(Arg::with_name("threads")
+            .long("threads")
+            .help("Amount of threads for parallel algorithms, 0 uses one per core")
+            .default_value("0"))
+        .arg(Arg::with_name("mbc-cutoff")
+            .long("mbc-cutoff")
+            .help("Smallest subproblem that parallel marriage-before-conquest splits into tasks")
+            .default_value("4096"))
+        .arg(Arg::with_name("mbc-median")
+            .long("mbc-median")
+            .help("How marriage-before-conquest chooses medians: from a random sample, or exactly by median of medians")
+            .possible_values(&["sampled", "exact"])
+            .default_value("sampled"))
+        .arg(Arg::with_name("mbc-sample-size")
+            .long("mbc-sample-size")
+            .help("Amount of randomly chosen items that sampled medians are the median of, at least 2")
+            .default_value("5"))
+        .arg(Arg::with_name("updates")
+            .long("updates")
+            .help("Amount of points that arrive and leave the window of --dynamic and --batch")
+            .default_value("100"))
+        .arg(Arg::with_name("directions")
+            .long("directions")
+            .help("Amount of directions the approximate hull samples, at least 3")
+            .default_value("64"))
+        .arg(Arg::with_name("file")
+            .long("file")
+            .value_name("PATH")
+            .takes_value(true)
+            .conflicts_with_all(&["sweep", "compare", "save-baseline", "stream"])
+            .help("Computes the hull of a point file, one \"x y\" per line, reading it in chunks."))
+        .arg(Arg::with_name("chunk-size")
+            .long("chunk-size")
+            .help("Amount of points of --file in memory at a time")
+            .default_value("1000000"))
+        .arg(Arg::with_name("generate-file")
+            .long("generate-file")
+            .value_name("PATH")
+            .takes_value(true)
+            .conflicts_with_all(&["sweep", "compare", "save-baseline", "stream", "file"])
+            .help("Writes --points generated points to a file for --file, without removing duplicates."))
+        .arg(Arg::with_name("layers-svg")
+            .long("layers-svg")
+            .value_name("PATH")
+            .takes_value(true)
+            .conflicts_with_all(&["sweep", "compare", "save-baseline", "stream", "file", "generate-file"])
+            .help("Plots the convex layers of the generated points to an SVG file, one colour per layer."))
+        .arg(Arg::with_name("circle-svg")
+            .long("circle-svg")
+            .value_name("PATH")
+            .takes_value(true)
+            .conflicts_with_all(&["sweep", "compare", "save-baseline", "stream", "file", "generate-file", "layers-svg"])
+            .help("Plots the generated points, their hull and its smallest enclosing circle to an SVG file."))
+        .arg(Arg::with_name("measure")
+            .long("measure")
+            .conflicts_with_all(&["sweep", "stream", "file", "generate-file"])
+            .help("Prints the diameter, width, smallest enclosing rectangles and circle of the hull of the points."))
+        .arg(Arg::with_name("contains")
+            .long("contains")
+            .value_name("N")
+            .takes_value(true)
+            .conflicts_with_all(&["sweep", "stream", "file", "generate-file"])
+            .help("Locates N more generated points against the hull of the points, timing the batch query."))
+        .arg(Arg::with_name("stream")
+            .long("stream")
+            .conflicts_with_all(&["sweep", "compare", "save-baseline"])
+            .help("Reads points from stdin, one \"x y\" per line, and prints their hull when the input ends."))
+        .arg(Arg::with_name("every")
+            .long("every")
+            .value_name("N")
+            .takes_value(true)
+            .requires("stream")
+            .help("Also prints the hull of the stream after every N points."))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .takes_value(true)
+            .help("Seed for the point generator and sampled medians. A random seed is used and printed if omitted."))
+        .arg(Arg::with_name("save-baseline")
+            .long("save-baseline")
+            .value_name("NAME")
+            .takes_value(true)
+            .conflicts_with("sweep")
+            .help("Stores the statistics of every algorithm as the named baseline."))
+        .arg(Arg::with_name("compare")
+            .long("compare")
+            .value_name("NAME")
+            .takes_value(true)
+            .conflicts_with("sweep")
+            .help("Reruns the dataset and, without algorithm flags, the algorithms of the named baseline, and fails on \
+                   significant regressions."))
+        .arg(Arg::with_name("threshold")
+            .long("threshold")
+            .help("Smallest change in mean time or measure, in percent, that --compare reports")
+            .default_value("5"))
+        .arg(Arg::with_name("raw")
+            .long("raw")
+            .help("Print every sample in addition to the summary."))
         .arg(Arg::with_name("generator")
             .short("g")
             .long("generator")
             .help("The point generator to use.")
-            .possible_values(&vec!["square", "circle", "exp"])
+            .possible_values(&["square", "circle", "exp", "polygon"])
             .default_value("square"))
+        .arg(Arg::with_name("ordered")
+            .long("ordered")
+            .help("Keeps the generated points in order, with duplicates, instead of deduplicating and shuffling them."))
         .arg(Arg::with_name("all")
             .short("a")
             .long("all")
@@ -159,633 +253,788 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .arg(Arg::with_name("incremental")
             .long("incremental")
             .help("Runs the incremental algorithm."))
+        .arg(Arg::with_name("incremental-in-place")
+            .long("incremental-in-place")
+            .help("Runs the allocation-free incremental algorithm."))
         .arg(Arg::with_name("gift")
             .long("gift")
             .help("Runs the gift wrapping algorithm."))
+        .arg(Arg::with_name("gift-in-place")
+            .long("gift-in-place")
+            .help("Runs the allocation-free gift wrapping algorithm."))
+        .arg(Arg::with_name("gift-simd")
+            .long("gift-simd")
+            .help("Runs the gift wrapping algorithm with vectorised wrapping steps."))
+        .arg(Arg::with_name("gift-indexed")
+            .long("gift-indexed")
+            .help("Runs the gift wrapping algorithm with wrapping steps that search a kd-tree."))
         .arg(Arg::with_name("chan")
             .long("chan")
             .help("Runs Chan's algorithm."))
+        .arg(Arg::with_name("chan-simd")
+            .long("chan-simd")
+            .help("Runs Chan's algorithm with vectorised wrapping steps."))
         .arg(Arg::with_name("mbc")
             .long("mbc")
             .help("Runs the marriage-before-conquest algorithm."))
+        .arg(Arg::with_name("dc")
+            .long("dc")
+            .help("Runs the divide and conquer algorithm, which merges the hulls of both halves."))
+        .arg(Arg::with_name("parallel")
+            .long("parallel")
+            .help("Runs the incremental algorithm on chunks in parallel and merges their hulls."))
+        .arg(Arg::with_name("parallel-mbc")
+            .long("parallel-mbc")
+            .help("Runs the marriage-before-conquest algorithm with subproblems in parallel."))
+        .arg(Arg::with_name("dynamic")
+            .long("dynamic")
+            .help("Slides a window over the points, updating a dynamic hull."))
+        .arg(Arg::with_name("batch")
+            .long("batch")
+            .help("Slides a window over the points, recomputing the hull with the incremental algorithm."))
+        .arg(Arg::with_name("online")
+            .long("online")
+            .help("Inserts the points one at a time into an online hull."))
+        .arg(Arg::with_name("approx")
+            .long("approx")
+            .help("Approximates the hull by extreme points in --directions directions, and measures its error."))
+        .arg(Arg::with_name("melkman")
+            .long("melkman")
+            .requires("ordered")
+            .help("Runs Melkman's algorithm, which needs the points in order along a simple polygon."))
+        .arg(Arg::with_name("layers")
+            .long("layers")
+            .help("Peels the points into convex layers."))
+        .arg(Arg::with_name("layers-peel")
+            .long("layers-peel")
+            .help("Peels the points into convex layers by computing the hull of the remaining points for every layer."))
         .get_matches();
-    let point_count = matches.value_of("points").unwrap().parse::<i64>().unwrap();
+    if matches.is_present("stream") {
+        let every = match matches.value_of("every") {
+            Some(every) => Some(every.parse::<usize>()?),
+            None => None,
+        };
+        if every == Some(0) {
+            return Err("--every needs at least one point".into());
+        }
+        return run_stream(every);
+    }
+
     let sample_count = matches.value_of("samples").unwrap().parse::<usize>().unwrap();
-    let point_generator_strategy = match matches.value_of("generator").unwrap() {
+    if sample_count == 0 {
+        return Err("At least one sample is required".into());
+    }
+    if matches.value_of("directions").unwrap().parse::<usize>()? < 3 {
+        return Err("The approximate hull needs at least 3 directions".into());
+    }
+    let mbc_median = match matches.value_of("mbc-median").unwrap() {
+        "exact" => MedianSelection::Exact,
+        _ => match matches.value_of("mbc-sample-size").unwrap().parse::<usize>()? {
+            0 | 1 => return Err("Sampled medians need a sample of at least two items".into()),
+            sample_size => MedianSelection::Sampled(sample_size),
+        },
+    };
+    // A single sample has no variance, so it can't tell a change in timing from noise.
+    if sample_count < 2 && (matches.is_present("compare") || matches.is_present("save-baseline")) {
+        return Err("Baselines need at least two samples".into());
+    }
+    let warmup_count = matches.value_of("warmup").unwrap().parse::<usize>().unwrap();
+    let threshold = matches.value_of("threshold").unwrap().parse::<f64>()? / 100.0;
+    let threads = matches.value_of("threads").unwrap().parse::<usize>()?;
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()?;
+
+    // Comparisons rerun the exact dataset the baseline was measured on.
+    let compare_with = match matches.value_of("compare") {
+        Some(name) => Some((name, Baseline::load(name)?)),
+        None => None,
+    };
+    let dataset = match &compare_with {
+        Some((_, baseline)) => baseline.dataset.clone(),
+        None => Dataset {
+            generator: matches.value_of("generator").unwrap().to_string(),
+            points: matches.value_of("points").unwrap().parse::<i64>().unwrap(),
+            seed: match matches.value_of("seed") {
+                Some(seed) => seed.parse::<u64>()?,
+                None => rand::random(),
+            },
+            ordered: matches.is_present("ordered"),
+        },
+    };
+    let point_count = dataset.points;
+    let point_generator_strategy = match dataset.generator.as_str() {
         "square" => PointGeneratorStrategy::Square,
         "circle" => PointGeneratorStrategy::Circle,
         "exp" => PointGeneratorStrategy::Exp,
+        "polygon" => PointGeneratorStrategy::Polygon,
         g => panic!("{} is not a known generator.", g),
     };
-    let points = generate_points(point_count, point_generator_strategy);
-    let csv = matches.is_present("csv");
-    if !csv {
-        println!("Point count: {:?}", point_count);
-        println!("Sample count: {:?}", sample_count);
-        println!("Point generator: {:?}", point_generator_strategy);
-        println!("Deduped point count: {:?}", points.len());
-    }
+    let mut rng = StdRng::seed_from_u64(dataset.seed);
+    let format = if matches.is_present("csv") { Format::Csv } else { Format::Human };
+
     let run_all = matches.is_present("all");
+    let settings = Settings {
+        sample_count,
+        warmup_count,
+        format,
+        raw: matches.is_present("raw"),
+        mbc_cutoff: matches.value_of("mbc-cutoff").unwrap().parse::<usize>()?,
+        mbc_median,
+        seed: dataset.seed,
+        updates: matches.value_of("updates").unwrap().parse::<usize>()?,
+        directions: matches.value_of("directions").unwrap().parse::<usize>()?,
+    };
 
-    if run_all || matches.is_present("incremental") {
-        run_algorithm(&points, Algorithm::Incremental, sample_count, csv);
+    if let Some(path) = matches.value_of("generate-file") {
+        write_point_file(path, point_count, point_generator_strategy, &mut rng)?;
+        if format == Format::Human {
+            println!("Wrote {} {:?} points with seed {} to {}", point_count, point_generator_strategy, dataset.seed, path);
+        }
+        return Ok(());
+    }
+    if let Some(path) = matches.value_of("file") {
+        let chunk_size = matches.value_of("chunk-size").unwrap().parse::<usize>()?;
+        if chunk_size == 0 {
+            return Err("Chunks must hold at least one point".into());
+        }
+        let report = run_file(path, chunk_size, &settings)?;
+        report.print(format);
+        return Ok(());
     }
 
-    if run_all || matches.is_present("gift") {
-        run_algorithm(&points, Algorithm::Gift, sample_count, csv);
+    let flags = [
+        ("incremental", Algorithm::Incremental),
+        ("incremental-in-place", Algorithm::IncrementalInPlace),
+        ("gift", Algorithm::Gift),
+        ("gift-in-place", Algorithm::GiftInPlace),
+        ("gift-simd", Algorithm::GiftSimd),
+        ("gift-indexed", Algorithm::GiftIndexed),
+        ("chan", Algorithm::Chan),
+        ("chan-simd", Algorithm::ChanSimd),
+        ("mbc", Algorithm::MBC),
+        ("dc", Algorithm::DivideAndConquer),
+        ("parallel", Algorithm::Parallel),
+        ("parallel-mbc", Algorithm::ParallelMBC),
+        ("dynamic", Algorithm::Dynamic),
+        ("batch", Algorithm::Batch),
+        ("online", Algorithm::Online),
+        ("approx", Algorithm::Approximate),
+        ("melkman", Algorithm::Melkman),
+        ("layers", Algorithm::Layers),
+        ("layers-peel", Algorithm::LayersPeel),
+    ];
+    let mut algorithms: Vec<Algorithm> = flags
+        .iter()
+        // Melkman's algorithm only finds the hull of ordered points, so --all skips it otherwise.
+        .filter(|(flag, _)| (run_all && (*flag != "melkman" || dataset.ordered)) || matches.is_present(flag))
+        .map(|(_, algorithm)| *algorithm)
+        .collect();
+    // Without algorithm flags a comparison reruns the algorithms of its baseline, and with them
+    // at least one has to be in it, or nothing would be compared.
+    if let Some((name, baseline)) = &compare_with {
+        if algorithms.is_empty() {
+            algorithms = baseline.algorithms
+                .iter()
+                .map(|a| flags
+                    .iter()
+                    .map(|(_, algorithm)| *algorithm)
+                    .find(|algorithm| format!("{:?}", algorithm) == *a)
+                    .ok_or_else(|| format!("Baseline {} has the unknown algorithm {}", name, a)))
+                .collect::<Result<_, _>>()?;
+        } else if !algorithms.iter().any(|a| baseline.algorithms.contains(&format!("{:?}", a))) {
+            return Err(format!("Baseline {} has none of the selected algorithms, only {}", name, baseline.algorithms.join(", ")).into());
+        }
     }
 
-    if run_all || matches.is_present("chan") {
-        run_algorithm(&points, Algorithm::Chan, sample_count, csv);
+    if let Some(range) = matches.value_of("sweep") {
+        let (from, to) = sweep::parse_range(range)?;
+        let steps = matches.value_of("steps").unwrap().parse::<usize>()?;
+        let sizes = sweep::log_spaced_sizes(from, to, steps);
+        if format == Format::Human {
+            println!("Sweep sizes: {:?}", sizes);
+            println!("Sample count: {:?}", sample_count);
+            println!("Warm-up count: {:?}", warmup_count);
+            println!("Point generator: {:?}", point_generator_strategy);
+            println!("Seed: {:?}", dataset.seed);
+        }
+        run_sweep(&algorithms, &sizes, point_generator_strategy, dataset.ordered, &mut rng, &settings);
+        return Ok(());
     }
 
-    if run_all || matches.is_present("mbc") {
-        run_algorithm(&points, Algorithm::MBC, sample_count, csv);
+    let points = generate_points(point_count, point_generator_strategy, dataset.ordered, &mut rng);
+    if format == Format::Human {
+        println!("Point count: {:?}", point_count);
+        println!("Sample count: {:?}", sample_count);
+        println!("Warm-up count: {:?}", warmup_count);
+        println!("Point generator: {:?}", point_generator_strategy);
+        println!("Seed: {:?}", dataset.seed);
+        println!("Threads: {:?}", rayon::current_num_threads());
+        if dataset.ordered {
+            println!("Ordered point count: {:?}", points.len());
+        } else {
+            println!("Deduped point count: {:?}", points.len());
+        }
+    }
+    if let Some(path) = matches.value_of("layers-svg") {
+        plot_layers(&points, path, format)?;
+        return Ok(());
+    }
+    if let Some(path) = matches.value_of("circle-svg") {
+        plot_circle(&points, path, format)?;
+        return Ok(());
+    }
+    if matches.is_present("measure") {
+        print_measures(&Hull::new(inc_ch(&points, &mut Metrics::new())), format);
+    }
+    if let Some(count) = matches.value_of("contains") {
+        let queries = generate_points(count.parse::<i64>()?, point_generator_strategy, false, &mut rng);
+        locate_points(&points, &queries, format);
     }
 
-    Ok(())
-}
+    let reports: Vec<Report> = algorithms
+        .into_iter()
+        .map(|algorithm| {
+            let report = run_algorithm(&points, algorithm, &settings);
+            report.print(format);
+            report
+        })
+        .collect();
+    print_speedups(&reports, format);
 
-fn run_algorithm(points: &Vec<Point>, algorithm: Algorithm, sample_count: usize, csv: bool) {
-    if !csv {
-        println!("------- Running {:?} {} times -------", algorithm, sample_count);
-    }
+    let regressions = match &compare_with {
+        Some((name, baseline)) => compare_with_baseline(name, baseline, &reports, threshold, format),
+        None => 0,
+    };
 
-    for _ in 0..sample_count {
-        match algorithm {
-            Algorithm::Incremental => {
-                let mut inc_ch_test_data = TestData::new();
-                let inc_ch_output_ch = inc_ch(&points, &mut inc_ch_test_data);
-                if csv {
-                    inc_ch_test_data.print_data_csv(algorithm, points.len(), inc_ch_output_ch.len());
-                } else {
-                    inc_ch_test_data.print_data(algorithm, points.len(), inc_ch_output_ch.len());
-                }
-            }
-            Algorithm::Gift => {
-                let mut gift_ch_test_data = TestData::new();
-                let hull = gift_ch(&points, &mut gift_ch_test_data);
-                if csv {
-                    gift_ch_test_data.print_data_csv(algorithm, points.len(), hull.len());
-                } else {
-                    gift_ch_test_data.print_data(algorithm, points.len(), hull.len());
-                }
-            }
-            Algorithm::Chan => {
-                let mut ch_ch_test_data = TestData::new();
-                let test = ch_ch(&points, &mut ch_ch_test_data);
-                if csv {
-                    ch_ch_test_data.print_data_csv(algorithm, points.len(), test.len());
-                } else {
-                    ch_ch_test_data.print_data(algorithm, points.len(), test.len());
-                }
-            }
-            Algorithm::MBC => {
-                let mut mbc_ch_test_data = MBCTestData::new();
-                let mbc_ch = mbc_ch(points.clone(), &mut mbc_ch_test_data);
-                if csv {
-                    mbc_ch_test_data.print_data_csv(points.len(), mbc_ch.len());
-                } else {
-                    mbc_ch_test_data.print_data(points.len(), mbc_ch.len());
-                }
-            }
+    if let Some(name) = matches.value_of("save-baseline") {
+        let baseline = Baseline {
+            dataset,
+            algorithms: reports.iter().map(|r| format!("{:?}", r.method)).collect(),
+            entries: reports
+                .iter()
+                .flat_map(|r| r.metrics().into_iter().map(move |(metric, s)| baseline::Entry {
+                    algorithm: format!("{:?}", r.method),
+                    metric: metric.to_string(),
+                    samples: s.samples,
+                    mean: s.mean,
+                    std_dev: s.std_dev,
+                }))
+                .collect(),
         };
+        let path = baseline.save(name)?;
+        if format == Format::Human {
+            println!("Saved baseline {} to {}", name, path.display());
+        }
     }
-}
 
-fn generate_circle_point() -> Point {
-    let mut rng = rand::thread_rng();
-    let a = rng.gen_range(0f64, 360f64);
-    let r = 4000f64 * f64::sqrt(rng.gen_range(0f64, 1f64));
-    let xx = (r * f64::cos(a)) as i64 + 3000;
-    let y = (r * f64::sin(a)) as i64 + 3000;
+    if regressions > 0 {
+        return Err(format!("{} regressions against baseline {}", regressions, compare_with.unwrap().0).into());
+    }
 
-    Point::new(xx, y)
+    Ok(())
 }
 
-fn generate_square_point() -> Point {
-    let mut rng = rand::thread_rng();
-    let x = rng.gen_range(0, 3600);
-    let y = rng.gen_range(0, 3600);
-
-    Point::new(x, y)
-}
+fn run_algorithm(points: &[Point], algorithm: Algorithm, settings: &Settings) -> Report {
+    if settings.format == Format::Human {
+        println!("------- Running {:?} {} times after {} warm-up runs -------", algorithm, settings.sample_count, settings.warmup_count);
+    }
 
-fn generate_exp_point(x: i64) -> Point {
-    Point::new(x, x.pow(2))
-}
+    for _ in 0..settings.warmup_count {
+        run_once(points, algorithm, settings);
+    }
 
-fn generate_points(count: i64, strategy: PointGeneratorStrategy) -> Vec<Point> {
-    let points: HashSet<_> = (0..count)
-        .map(|i| (match strategy {
-            PointGeneratorStrategy::Circle => generate_circle_point(),
-            PointGeneratorStrategy::Square => generate_square_point(),
-            PointGeneratorStrategy::Exp => generate_exp_point(i),
-        }))
+    let samples: Vec<Sample> = (0..settings.sample_count)
+        .map(|_| {
+            let sample = run_once(points, algorithm, settings);
+            if settings.raw {
+                sample.print(Method::Algorithm(algorithm), points.len(), settings.format);
+            }
+            sample
+        })
         .collect();
-
-    let points: Vec<_> = points.into_iter().collect();
-    points
+    Report::new(Method::Algorithm(algorithm), points.len(), &samples)
 }
 
-/// These methods calculate at which side of the line point c is
-/// The line is created between a and b.
-/// There are 3 different outcomes
-/// < 0  -> left turn
-/// == 0 -> on the line
-/// > 0  -> right turn
-fn dir(a: Point, b: Point, c: Point) -> i64 {
-    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
-}
-
-fn inc_ch(points: &Vec<Point>, test_struct: &mut TestData) -> Vec<Point> {
-    test_struct.set_start_time();
-    if points.len() <= 2 {
-        let set: HashSet<Point> = points.clone().drain(..).collect();
-        test_struct.set_end_time();
-        return Vec::from_iter(set.iter().map(|p| p.clone()));
-    }
-
-    let mut cloned_points = points.clone();
-    cloned_points.sort();
-    let mut asc_sorted = cloned_points.clone();
-    cloned_points.reverse();
-    let mut desc_sorted = cloned_points;
-    let mut lh: Vec<Point> = asc_sorted.drain(0..2).collect();
-    let mut uh: Vec<Point> = desc_sorted.drain(0..2).collect();
-
-    generate_upper_hull(test_struct, &mut asc_sorted, &mut lh);
-    generate_upper_hull(test_struct, &mut desc_sorted, &mut uh);
-
-    uh.pop();
-    uh.drain(0..1);
-    lh.append(&mut uh);
-    test_struct.set_end_time();
-    lh
-}
-
-fn generate_upper_hull(test_struct: &mut TestData, desc_sorted: &mut Vec<Point>, uh: &mut Vec<Point>) {
-    for cur_point in desc_sorted {
-        loop {
-            if uh.len() < 2 {
-                break;
+/// Prints the speedup of every parallel algorithm over its sequential counterpart, when both ran.
+fn print_speedups(reports: &[Report], format: Format) {
+    let find = |algorithm: Algorithm| reports.iter().find(|r| r.method == Method::Algorithm(algorithm));
+    for &(parallel, sequential) in SPEEDUPS.iter() {
+        if let (Some(p), Some(s)) = (find(parallel), find(sequential)) {
+            let speedup = s.time.mean / p.time.mean;
+            let threads = rayon::current_num_threads();
+            if format == Format::Csv {
+                println!("speedup,{:?},{:?},{},{}", parallel, sequential, threads, speedup);
+            } else {
+                println!("Speedup of {:?} over {:?} on {} threads: {:.2}x (efficiency {:.0}%)",
+                         parallel, sequential, threads, speedup, speedup / threads as f64 * 100.0);
             }
-            let direction = dir(uh[uh.len() - 2], uh[uh.len() - 1], *cur_point);
-            if direction < 0 {
-                break;
-            }
-            test_struct.increment_turn(direction);
-            uh.pop();
         }
-
-        uh.push(*cur_point);
-        let d = dir(uh[uh.len() - 2], uh[uh.len() - 1], *cur_point);
-        test_struct.increment_turn(d);
     }
 }
 
-fn gift_ch(points: &Vec<Point>, test_struct: &mut TestData) -> Vec<Point> {
-    test_struct.set_start_time();
-    let mut hull = Vec::<Point>::new();
-    let mut hull_point: Point = match points.iter().min() {
-        Some(p) => *p,
-        None => return Vec::new(),
-    };
-    loop {
-        hull.push(hull_point);
-        let mut current_candidate: Point = match points.get(0) {
-            Some(p) => *p,
-            None => exit(8),
-        };
-
-        for j in points.clone() {
-            let direction = dir(hull[hull.len() - 1], current_candidate, j);
-            test_struct.increment_turn(direction);
-            if current_candidate == hull_point || direction > 0 {
-                current_candidate = j;
+/// Prints how every metric changed since the baseline and returns the amount of regressions.
+/// Timings and measures may move within `threshold` of the baseline mean, counters may not move
+/// at all.
+/// The significance of the changes is decided over all of the metrics together.
+fn compare_with_baseline(name: &str, baseline: &Baseline, reports: &[Report], threshold: f64, format: Format) -> usize {
+    if format == Format::Human {
+        println!("------- Comparison with baseline {} -------", name);
+    }
+    let mut compared = Vec::new();
+    for report in reports {
+        let algorithm = format!("{:?}", report.method);
+        for (metric, summary) in report.metrics() {
+            let metric_threshold = if report.counter(&metric).is_some() { 0.0 } else { threshold };
+            match baseline.find(&algorithm, &metric) {
+                Some(old) => compared.push((algorithm.clone(), metric, old, summary, metric_threshold)),
+                None if format == Format::Human => println!("{} {}: not in baseline", algorithm, metric),
+                None => (),
             }
         }
-        hull_point = current_candidate;
-        if current_candidate == hull[0] {
-            break;
+    }
+    for missing in baseline.algorithms.iter().filter(|&a| !reports.iter().any(|r| format!("{:?}", r.method) == *a)) {
+        if format == Format::Human {
+            println!("{}: in baseline, but not run", missing);
         }
     }
-    test_struct.set_end_time();
-    hull
-}
 
-fn ch_ch(points: &Vec<Point>, test_struct: &mut TestData) -> Vec<Point> {
-    test_struct.set_start_time();
-    for i in 1..(log_2(log_2(points.len() as i32) as i32) + 2) {
-        let (hull, success) = uh_with_size(points, i64::pow(2, i64::pow(2, i) as u32), test_struct);
-        if success {
-            test_struct.set_end_time();
-            return hull;
+    let comparisons: Vec<(&baseline::Entry, &Summary, f64)> = compared
+        .iter()
+        .map(|&(_, _, old, summary, threshold)| (old, summary, threshold))
+        .collect();
+    let verdicts = baseline::compare_all(&comparisons);
+    let mut regressions = 0;
+    for ((algorithm, metric, old, summary, _), verdict) in compared.iter().zip(verdicts) {
+        if verdict == Verdict::Regressed {
+            regressions += 1;
+        }
+        let change = if old.mean == 0.0 { 0.0 } else { (summary.mean - old.mean) / old.mean * 100.0 };
+        if format == Format::Csv {
+            println!("compare,{},{},{},{},{},{:?}", algorithm, metric, old.mean, summary.mean, change, verdict);
+        } else {
+            println!("{} {}: {:.1} -> {:.1} ({:+.2}%) {:?}", algorithm, metric, old.mean, summary.mean, change, verdict);
         }
     }
-    test_struct.set_end_time();
-    Vec::<Point>::new()
+    if format == Format::Human {
+        println!("-------------------------------------");
+        println!();
+    }
+    regressions
 }
 
-fn uh_with_size(points: &Vec<Point>, h: i64, test_struct: &mut TestData) -> (Vec<Point>, bool) {
-    let mut cloned_points = points.clone();
-    let mut tmp_hull = Vec::<Point>::new();
-    let mut upper_hull = Vec::<Point>::new();
-    let mut lower_hull = Vec::<Point>::new();
-
-    let mut m = points.len() / (h as usize);
-    if m == 0 {
-        m = 1
-    }
-    loop {
-        let min = usize::min(cloned_points.len(), m);
-        if min == 0 {
-            break;
+/// Runs every algorithm on each of the point counts and fits the mean time and turn count
+/// against the complexity models in `sweep`.
+fn run_sweep(algorithms: &[Algorithm], sizes: &[i64], strategy: PointGeneratorStrategy, ordered: bool, rng: &mut StdRng,
+             settings: &Settings) {
+    let format = settings.format;
+    let run_settings = Settings { raw: false, ..*settings };
+    let mut reports: Vec<Vec<Report>> = algorithms.iter().map(|_| Vec::new()).collect();
+    for &size in sizes {
+        let points = generate_points(size, strategy, ordered, rng);
+        for (i, &algorithm) in algorithms.iter().enumerate() {
+            reports[i].push(run_algorithm(&points, algorithm, &run_settings));
         }
-        let mut ps: Vec<Point> = cloned_points.drain(0..min).collect();
-        tmp_hull.append(&mut gift_ch(&mut ps, test_struct))
     }
 
+    for (algorithm, reports) in algorithms.iter().zip(reports) {
+        if format == Format::Csv {
+            for r in &reports {
+                println!("sweep,{:?},{},{},{},{}", algorithm, r.input_size, r.output_size, r.time.mean,
+                         r.counter("total_turns").map_or(String::new(), |s| s.mean.to_string()));
+            }
+        } else {
+            println!("------- Method: {:?} sweep -------", algorithm);
+            println!("{:>12} {:>8} {:>16} {:>16}", "n", "h", "mean time_ns", "mean turns");
+            for r in &reports {
+                println!("{:>12} {:>8} {:>16.0} {:>16}", r.input_size, r.output_size, r.time.mean,
+                         r.counter("total_turns").map_or(String::from("-"), |s| format!("{:.0}", s.mean)));
+            }
+        }
+
+        let mut costs: Vec<(&str, Vec<sweep::Observation>)> = vec![
+            ("time_ns", reports.iter().map(|r| sweep::Observation { n: r.input_size, h: r.output_size, cost: r.time.mean }).collect()),
+        ];
+        if reports.iter().all(|r| r.counter("total_turns").is_some()) {
+            costs.push(("total_turns", reports
+                .iter()
+                .map(|r| sweep::Observation { n: r.input_size, h: r.output_size, cost: r.counter("total_turns").unwrap().mean })
+                .collect()));
+        }
+        for (metric, observations) in costs {
+            let fits = sweep::fit(&observations);
+            if fits.is_empty() {
+                if format == Format::Human {
+                    println!("{} has no fit, as it is 0 at every size", metric);
+                }
+            } else if format == Format::Csv {
+                for f in &fits {
+                    println!("fit,{:?},{},{},{},{}", algorithm, metric, f.model.name(), f.constant, f.relative_error);
+                }
+            } else {
+                println!("{} best fits {} with c = {:.4} (rms relative error {:.1}%)",
+                         metric, fits[0].model.name(), fits[0].constant, fits[0].relative_error * 100.0);
+                for f in &fits[1..] {
+                    println!("    {:<8} c = {:<12.4} rms relative error {:.1}%", f.model.name(), f.constant, f.relative_error * 100.0);
+                }
+            }
+        }
+        if format == Format::Human {
+            println!("-------------------------------------");
+            println!();
+        }
+    }
+}
 
-    let min_point = match points.iter().min() {
-        Some(point) => *point,
-        None => exit(7),
+fn run_once(points: &[Point], algorithm: Algorithm, settings: &Settings) -> Sample {
+    let mut metrics = Metrics::new();
+    // Algorithms that consume or reorder their input get a copy, made before profiling so only the
+    // algorithm itself is counted.
+    let mut input = match algorithm {
+        Algorithm::IncrementalInPlace | Algorithm::GiftInPlace | Algorithm::MBC | Algorithm::ParallelMBC => points.to_vec(),
+        _ => Vec::new(),
     };
-    let max_point = match points.iter().max() {
-        Some(point) => *point,
-        None => exit(7),
+    let mut approximation = None;
+    // The hull is kept for its shape, which is measured after profiling.
+    let mut hull = None;
+    let mut keep = |vertices: Vec<Point>| {
+        let len = vertices.len();
+        hull = Some(vertices);
+        len
     };
-    let mut upper_point: Point = min_point.clone();
-    let mut lower_point: Point = max_point.clone();
-
-    for _ in 0..h {
-        if upper_hull.len() < 2 || upper_hull[upper_hull.len() - 1] != max_point {
-            upper_hull.push(upper_point);
+    let allocations = alloc::Snapshot::start();
+    let hull_len = match algorithm {
+        Algorithm::Incremental => keep(inc_ch(points, &mut metrics)),
+        Algorithm::IncrementalInPlace => {
+            // The hull is left at the front of the input.
+            let len = inc_ch_in_place(&mut input, &mut metrics);
+            input.truncate(len);
+            keep(input)
         }
-        if lower_hull.len() < 2 || lower_hull[lower_hull.len() - 1] != min_point {
-            lower_hull.push(lower_point);
+        Algorithm::Gift => keep(gift_ch(points, &mut metrics)),
+        Algorithm::GiftInPlace => {
+            // The hull is left at the front of the input.
+            let len = gift_ch_in_place(&mut input, &mut metrics);
+            input.truncate(len);
+            keep(input)
         }
-
-        if (upper_hull.len() > 2 || lower_hull.len() > 2)
-            && upper_hull[upper_hull.len() - 1] == max_point
-            && lower_hull[lower_hull.len() - 1] == min_point {
-            break;
+        Algorithm::GiftSimd => keep(gift_ch_simd(points, &mut metrics)),
+        Algorithm::GiftIndexed => keep(gift_ch_indexed(points, &mut metrics)),
+        Algorithm::Chan => keep(ch_ch(points, &mut metrics)),
+        Algorithm::ChanSimd => keep(ch_ch_simd(points, &mut metrics)),
+        Algorithm::MBC => keep(mbc_ch_with(input, &mut metrics, settings.mbc_median, settings.seed)),
+        Algorithm::DivideAndConquer => keep(dc_ch(points, &mut metrics)),
+        Algorithm::Parallel => keep(par_ch(points, &mut metrics)),
+        Algorithm::ParallelMBC => keep(mbc_ch_par(input, &mut metrics, settings.mbc_cutoff, settings.mbc_median, settings.seed)),
+        Algorithm::Melkman => keep(melkman_ch(points, &mut metrics)),
+        // Layers are counted, and the outermost one is reported as the hull.
+        Algorithm::Layers => keep(outermost(convex_layers(points, &mut metrics))),
+        Algorithm::LayersPeel => keep(outermost(peel_layers(points, &mut metrics))),
+        Algorithm::Dynamic => slide_dynamic(points, settings.updates, &mut metrics),
+        Algorithm::Batch => slide_batch(points, settings.updates, &mut metrics),
+        Algorithm::Online => {
+            let mut hull = OnlineHull::new();
+            metrics.set_start_time();
+            hull.extend(points.iter().copied());
+            let vertices = hull.hull();
+            metrics.set_end_time();
+            metrics.merge_counters(hull.metrics());
+            keep(vertices)
         }
+        Algorithm::Approximate => {
+            let approximate = approx_ch(points, settings.directions, &mut metrics);
+            let hull_len = approximate.hull.len();
+            approximation = Some(approximate);
+            hull_len
+        }
+    };
+    allocations.record(&mut metrics);
+    // The approximation is validated against the exact hull after it has been measured.
+    if let Some(approximate) = approximation {
+        let exact = inc_ch(points, &mut Metrics::new());
+        let error = exact.iter().map(|&p| distance_outside(&approximate.hull, p)).fold(0.0, f64::max);
+        metrics.measure("error_bound", approximate.error_bound);
+        metrics.measure("error", error);
+        hull = Some(approximate.hull);
+    }
+    let shape = hull.map(|vertices| Shape::of(&Hull::new(vertices)));
+    Sample::new(hull_len, shape, metrics)
+}
 
-        let mut current_upper_candidate = match tmp_hull.get_mut(0) {
-            Some(point) => *point,
-            None => {
-                exit(5)
-            }
-        };
+/// The outermost of convex layers, which is the hull of the points.
+fn outermost(layers: Vec<Layer>) -> Vec<Point> {
+    layers.into_iter().next().map_or_else(Vec::new, |layer| layer.hull)
+}
 
-        let mut current_lower_candidate = current_upper_candidate.clone();
+/// Computes the hull of a point file with `external_ch`, reading it again for every run.
+fn run_file(path: &str, chunk_size: usize, settings: &Settings) -> Result<Report, Box<dyn std::error::Error>> {
+    let method = Method::External;
+    if settings.format == Format::Human {
+        println!("------- Running {:?} on {} {} times after {} warm-up runs -------", method, path, settings.sample_count, settings.warmup_count);
+    }
+    let run = || -> Result<Sample, Box<dyn std::error::Error>> {
+        let reader = BufReader::new(File::open(path).map_err(|e| format!("Could not open {}: {}", path, e))?);
+        let mut metrics = Metrics::new();
+        let allocations = alloc::Snapshot::start();
+        let hull = external_ch(reader, chunk_size, &mut metrics)?;
+        allocations.record(&mut metrics);
+        Ok(Sample::new(hull.len(), Some(Shape::of(&Hull::new(hull))), metrics))
+    };
+    for _ in 0..settings.warmup_count {
+        run()?;
+    }
+    let samples = (0..settings.sample_count).map(|_| run()).collect::<Result<Vec<Sample>, _>>()?;
+    let input_size = samples[0].metrics.counter("points_read") as usize;
+    if settings.raw {
+        for sample in &samples {
+            sample.print(method, input_size, settings.format);
+        }
+    }
+    Ok(Report::new(method, input_size, &samples))
+}
 
-        for point in tmp_hull.clone() {
-            let dir1 = dir(upper_hull[upper_hull.len() - 1], current_upper_candidate, point);
-            test_struct.increment_turn(dir1);
-            if current_upper_candidate == upper_point || dir1 > 0 {
-                current_upper_candidate = point;
-            }
-            let dir2 = dir(lower_hull[lower_hull.len() - 1], current_lower_candidate, point);
-            test_struct.increment_turn(dir2);
-            if current_lower_candidate == lower_point || dir2 > 0 {
-                current_lower_candidate = point;
+/// Prints the rotating calipers measurements of a hull, and its smallest enclosing circle.
+fn print_measures(hull: &Hull, format: Format) {
+    let (a, b) = match hull.diameter() {
+        Some(diameter) => diameter,
+        None => return,
+    };
+    let circle = welzl(hull.vertices(), &mut rand::thread_rng(), &mut Metrics::new());
+    let diameter = hull.diameter_length().unwrap();
+    let width = hull.width().unwrap();
+    let area = hull.min_area_rectangle().unwrap();
+    let perimeter = hull.min_perimeter_rectangle().unwrap();
+    if format == Format::Csv {
+        println!("measure,hull_points,{}", hull.len());
+        println!("measure,diameter,{}", diameter);
+        println!("measure,width,{}", width.width);
+        println!("measure,min_area_rectangle,{},{},{}", area.length, area.height, area.area());
+        println!("measure,min_perimeter_rectangle,{},{},{}", perimeter.length, perimeter.height, perimeter.perimeter());
+        if let Some(circle) = circle {
+            let (x, y) = circle.centre();
+            println!("measure,min_enclosing_circle,{},{},{},{}", x, y, circle.radius_squared(), circle.radius());
+        }
+    } else {
+        println!("------- Measures of the hull of {} points -------", hull.len());
+        println!("Diameter: {:.3} from ({}, {}) to ({}, {})", diameter, a.x, a.y, b.x, b.y);
+        println!("Width: {:.3} from the edge ({}, {}) to ({}, {}) to the point ({}, {})", width.width,
+                 width.edge.0.x, width.edge.0.y, width.edge.1.x, width.edge.1.y, width.opposite.x, width.opposite.y);
+        println!("Smallest area rectangle: {:.3} x {:.3}, area {:.3}, corners {:.3?}", area.length, area.height, area.area(), area.corners);
+        println!("Smallest perimeter rectangle: {:.3} x {:.3}, perimeter {:.3}, corners {:.3?}",
+                 perimeter.length, perimeter.height, perimeter.perimeter(), perimeter.corners);
+        match circle {
+            Some(circle) => {
+                let (x, y) = circle.centre();
+                println!("Smallest enclosing circle: centre ({}, {}), radius squared {}, radius {:.3}", x, y, circle.radius_squared(), circle.radius());
             }
+            None => println!("Smallest enclosing circle: not found, as the points span {} or more", MAX_SPAN),
         }
-
-        lower_point = current_lower_candidate;
-        upper_point = current_upper_candidate;
+        println!("-------------------------------------");
+        println!();
     }
-
-    let succ = upper_hull[upper_hull.len() - 1] == max_point
-        && lower_hull[lower_hull.len() - 1] == min_point;
-    lower_hull.pop();
-    lower_hull.drain(0..1);
-    upper_hull.append(&mut lower_hull);
-    (upper_hull, succ)
 }
 
-const fn num_bits<T>() -> usize { std::mem::size_of::<T>() * 8 }
-
-fn log_2(x: i32) -> u32 {
-    assert!(x > 0);
-    num_bits::<i32>() as u32 - x.leading_zeros() - 1
+/// Locates the queries against the hull of the points, and prints how many are where and how
+/// long building the index and the batch query took.
+fn locate_points(points: &[Point], queries: &[Point], format: Format) {
+    let hull = Hull::new(inc_ch(points, &mut Metrics::new()));
+    let start = Instant::now();
+    let index = HullIndex::new(&hull);
+    let build = start.elapsed();
+    let start = Instant::now();
+    let locations = index.contains_all(queries);
+    let query = start.elapsed();
+    let count = |location| locations.iter().filter(|&&l| l == location).count();
+    let (inside, boundary, outside) = (count(Location::Inside), count(Location::Boundary), count(Location::Outside));
+    if format == Format::Csv {
+        println!("contains,{},{},{},{},{},{}", hull.len(), queries.len(), inside, boundary, outside, query.as_secs_f64());
+    } else {
+        println!("------- Locating {} points against a hull of {} -------", queries.len(), hull.len());
+        println!("Inside: {}, boundary: {}, outside: {}", inside, boundary, outside);
+        println!("Index built in {:?}, queried in {:?}", build, query);
+        println!("-------------------------------------");
+        println!();
+    }
 }
 
-fn bridge(points: &[Point], a: i64, test_data: &mut MBCTestData) -> (Point, Point) {
-    test_data.bridges += 1;
-    if points.len() == 2 {
-        return if points[0].x < points[1].x {
-            (points[0], points[1])
+/// Writes the convex layers of the points to an SVG file and prints the size of every layer.
+fn plot_layers(points: &[Point], path: &str, format: Format) -> std::io::Result<()> {
+    let layers = convex_layers(points, &mut Metrics::new());
+    let hulls: Vec<Vec<Point>> = layers.iter().map(|layer| layer.hull.clone()).collect();
+    svg::write_svg(BufWriter::new(File::create(path)?), points, &hulls, &[])?;
+    for layer in &layers {
+        if format == Format::Csv {
+            println!("layer,{},{}", layer.depth, layer.hull.len());
         } else {
-            (points[1], points[0])
-        };
+            println!("Layer {}: {} points", layer.depth, layer.hull.len());
+        }
     }
-    let mut candidates = Vec::with_capacity(points.len());
-    let chunks = points.chunks_exact(2);
-    chunks.remainder().iter().for_each(|&p| candidates.push(p));
-    let pairs: Vec<_> = chunks
-        .map(|p| match p {
-            [p1, p2, ..] => if p1.x < p2.x { (*p1, *p2) } else { (*p2, *p1) },
-            _ => panic!("Chunks forgot how to chunk"),
-        })
-        .filter(|(p1, p2)|
-            if p1.x == p2.x {
-                if p1.y > p2.y {
-                    candidates.push(p1.clone());
-                } else {
-                    candidates.push(p2.clone());
-                }
-                false
-            } else { true }
-        )
-        .map(|(p1, p2)| (p1, p2, (p1.y as f64 - p2.y as f64) / (p1.x as f64 - p2.x as f64)))
-        .collect();
-
-    if pairs.is_empty() {
-        return bridge(&candidates, a, test_data);
+    if format == Format::Human {
+        println!("Wrote {} layers to {}", layers.len(), path);
     }
+    Ok(())
+}
 
-    let slopes: Vec<_> = pairs.iter().map(|(_, _, s)| s).cloned().collect();
-    let slope_median = find_sampled_median(&slopes, 5);
-
-    let small: Vec<_> = pairs
-        .iter()
-        .filter(|(_, _, s)| *s < slope_median)
-        .map(|(p1, p2, _)| (*p1, *p2))
-        .collect();
-    let equal: Vec<_> = pairs
-        .iter()
-        .filter(|(_, _, s)| *s == slope_median)
-        .map(|(p1, p2, _)| (*p1, *p2))
-        .collect();
-    let large: Vec<_> = pairs
-        .iter()
-        .filter(|(_, _, s)| *s > slope_median)
-        .map(|(p1, p2, _)| (*p1, *p2))
-        .collect();
+/// Writes the points, their hull and its smallest enclosing circle to an SVG file.
+fn plot_circle(points: &[Point], path: &str, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let mut metrics = Metrics::new();
+    let circle = match min_enclosing_circle(points, inc_ch, &mut metrics) {
+        Some(circle) => circle,
+        None if points.is_empty() => return Ok(()),
+        None => return Err(format!("No smallest enclosing circle, as the points span {} or more", MAX_SPAN).into()),
+    };
+    let hull = inc_ch(points, &mut Metrics::new());
+    svg::write_svg(BufWriter::new(File::create(path)?), points, &[hull], &[circle])?;
+    let (x, y) = circle.centre();
+    if format == Format::Csv {
+        println!("circle,{},{},{},{}", x, y, circle.radius_squared(), metrics.counter("circle_tests"));
+    } else {
+        println!("Smallest enclosing circle: centre ({}, {}), radius squared {}, {} tests", x, y, circle.radius_squared(), metrics.counter("circle_tests"));
+        println!("Wrote the circle to {}", path);
+    }
+    Ok(())
+}
 
-    let funny_slopes: Vec<_> = points
-        .iter()
-        .map(|p| p.y as f64 - slope_median * p.x as f64)
-        .collect();
-    let max_slope = funny_slopes
-        .iter()
-        .max_by(|f1, f2| f1.partial_cmp(f2).unwrap())
-        .cloned()
-        .unwrap();
-
-    let max = {
-        let mut max = Vec::new();
-        for i in 0..points.len() {
-            if (funny_slopes[i] - max_slope).abs() < 10e-10 {
-                max.push(points[i]);
-            }
+/// Writes `count` generated points to a file, one at a time, so any amount fits in memory.
+/// Unlike `generate_points` duplicates are kept, and the points are in generation order.
+fn write_point_file(path: &str, count: i64, strategy: PointGeneratorStrategy, rng: &mut StdRng) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let generate: fn(i64, &mut StdRng) -> Point = match strategy {
+        PointGeneratorStrategy::Circle => |_, rng| generate_circle_point(rng),
+        PointGeneratorStrategy::Square => |_, rng| generate_square_point(rng),
+        PointGeneratorStrategy::Exp => |i, _| generate_exp_point(i),
+        // A polygon's vertices are only known once all of them are sorted, so it is built in memory.
+        PointGeneratorStrategy::Polygon => {
+            io::write_points(&mut out, &generate_polygon(count, rng))?;
+            return out.flush();
         }
-        max
     };
-
-    let pk = max
-        .iter()
-        .min_by_key(|p| p.x)
-        .cloned()
-        .unwrap();
-    let pm = max
-        .iter()
-        .max_by_key(|p| p.x)
-        .cloned()
-        .unwrap();
-    if pk.x <= a && pm.x > a {
-        return (pk, pm);
+    for i in 0..count {
+        let point = generate(i, rng);
+        writeln!(out, "{} {}", point.x, point.y)?;
     }
+    out.flush()
+}
 
-    if pm.x <= a {
-        large.iter().cloned().for_each(|(_, p)| candidates.push(p));
-        equal.iter().cloned().for_each(|(_, p)| candidates.push(p));
-        small.iter().cloned().for_each(|(p1, p2)| {
-            candidates.push(p1);
-            candidates.push(p2);
-        });
-    }
+/// Feeds the points on stdin into an online hull and prints it, after every `every` points
+/// and when the input ends. Each hull is preceded by a comment with the amount of points read.
+fn run_stream(every: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let print = |hull: &OnlineHull, out: &mut std::io::StdoutLock| -> std::io::Result<()> {
+        writeln!(out, "# {} points", hull.points_seen())?;
+        io::write_points(&mut *out, &hull.hull())?;
+        writeln!(out)?;
+        out.flush()
+    };
 
-    if pk.x > a {
-        equal.iter().cloned().for_each(|(p, _)| candidates.push(p));
-        small.iter().cloned().for_each(|(p, _)| candidates.push(p));
-        large.iter().cloned().for_each(|(p1, p2)| {
-            candidates.push(p1);
-            candidates.push(p2);
-        });
+    let mut hull = OnlineHull::new();
+    let mut printed = false;
+    for point in io::read_points(std::io::stdin().lock()) {
+        hull.insert(point?);
+        printed = every.is_some_and(|every| hull.points_seen() % every == 0);
+        if printed {
+            print(&hull, &mut out)?;
+        }
     }
-
-    let candidates: Vec<_> = candidates.into_iter().collect();
-    bridge(&candidates, a, test_data)
+    if !printed {
+        print(&hull, &mut out)?;
+    }
+    Ok(())
 }
 
-fn find_sampled_median_x(points: &[Point], sample_size: usize) -> i64 {
-    let mut sample: Vec<i64> = points
-        .choose_multiple(&mut rand::thread_rng(), sample_size)
-        .map(|p| p.x)
-        .collect();
-    sample.sort();
-    sample[sample.len() / 2]
+/// Builds a dynamic hull of all but the last `updates` points, then slides the window over the
+/// rest: the next point arrives, the oldest leaves and the hull is read, `updates` times.
+/// Returns the size of the last hull.
+fn slide_dynamic(points: &[Point], updates: usize, metrics: &mut Metrics) -> usize {
+    let updates = usize::min(updates, points.len());
+    let window = points.len() - updates;
+    metrics.set_start_time();
+    let phase = Instant::now();
+    let mut hull = DynamicHull::new();
+    for &point in &points[..window] {
+        hull.insert(point);
+    }
+    let mut hull_len = hull.hull().len();
+    metrics.end_phase("build", phase);
+    let phase = Instant::now();
+    for i in 0..updates {
+        hull.insert(points[window + i]);
+        hull.remove(points[i]);
+        hull_len = hull.hull().len();
+    }
+    metrics.end_phase("updates", phase);
+    metrics.set_end_time();
+    metrics.merge_counters(hull.metrics());
+    hull_len
 }
 
-fn find_sampled_median<T: PartialOrd + Copy>(points: &[T], sample_size: usize) -> T {
-    let mut sample: Vec<T> = points
-        .choose_multiple(&mut rand::thread_rng(), sample_size)
-        .cloned()
-        .collect();
-    sample.sort_by(|f1, f2| f1.partial_cmp(f2).unwrap());
-    sample[sample.len() / 2]
+/// The window of `slide_dynamic`, with the hull recomputed by `inc_ch` after every update.
+fn slide_batch(points: &[Point], updates: usize, metrics: &mut Metrics) -> usize {
+    let updates = usize::min(updates, points.len());
+    let window = points.len() - updates;
+    let run = |window: &[Point], metrics: &mut Metrics| {
+        let mut run_metrics = Metrics::new();
+        let hull_len = inc_ch(window, &mut run_metrics).len();
+        metrics.merge_counters(&run_metrics);
+        hull_len
+    };
+    metrics.set_start_time();
+    let phase = Instant::now();
+    let mut hull_len = run(&points[..window], metrics);
+    metrics.end_phase("build", phase);
+    let phase = Instant::now();
+    for i in 1..=updates {
+        hull_len = run(&points[i..window + i], metrics);
+    }
+    metrics.end_phase("updates", phase);
+    metrics.set_end_time();
+    hull_len
 }
 
-fn mbc_ch(points: Vec<Point>, test_data: &mut MBCTestData) -> Vec<Point> {
-    test_data.time_start = std::time::SystemTime::now();
-    let lh_points: Vec<_> = points
-        .iter()
-        .map(|p| Point { x: p.x, y: -p.y })
-        .collect();
-    let lh = mbc_ch_inner(lh_points, test_data);
-    let mut real_lh: Vec<_> = lh
-        .iter()
-        .skip(1)
-        .take(lh.len() - 2)
-        .rev()
-        .map(|p| Point { x: p.x, y: -p.y })
-        .collect();
+fn generate_circle_point(rng: &mut StdRng) -> Point {
+    let a = rng.gen_range(0f64, 360f64);
+    let r = 4000f64 * f64::sqrt(rng.gen_range(0f64, 1f64));
+    let xx = (r * f64::cos(a)) as i64 + 3000;
+    let y = (r * f64::sin(a)) as i64 + 3000;
 
-    let mut hull = mbc_ch_inner(points, test_data);
-    hull.append(&mut real_lh);
-    test_data.time_end = std::time::SystemTime::now();
-    hull
+    Point::new(xx, y)
 }
 
-fn mbc_ch_inner(points: Vec<Point>, test_data: &mut MBCTestData) -> Vec<Point> {
-    test_data.recursions += 1;
-    if points.len() < 2 {
-        return points;
-    } else if points.len() == 2 {
-        return if points[0].x < points[1].x {
-            points
-        } else {
-            vec![points[1], points[0]]
-        };
-    }
-
-    let median_x = find_sampled_median_x(&points, 5);
+fn generate_square_point(rng: &mut StdRng) -> Point {
+    let x = rng.gen_range(0, 3600);
+    let y = rng.gen_range(0, 3600);
 
-    let (left_point, right_point) = bridge(&points, median_x, test_data);
+    Point::new(x, y)
+}
 
-    let left = {
-        let mut left: Vec<_> = points
-            .iter()
-            .filter(|p| p.x < left_point.x)
-            .cloned()
-            .collect();
-        left.push(left_point);
-        left
-    };
-    let right = {
-        let mut right: Vec<Point> = points
-            .into_iter()
-            .filter(|p| p.x > right_point.x)
-            .collect();
-        right.push(right_point);
-        right
-    };
+fn generate_exp_point(x: i64) -> Point {
+    Point::new(x, x.pow(2))
+}
 
-    let mut hull = mbc_ch_inner(left, test_data);
-    hull.append(&mut mbc_ch_inner(right, test_data));
-    hull
+/// A random simple polygon with up to `count` vertices, within the disc of `generate_circle_point`.
+fn generate_polygon(count: i64, rng: &mut StdRng) -> Vec<Point> {
+    random_simple_polygon(count as usize, 4000, rng)
+        .into_iter()
+        .map(|p| Point::new(p.x + 3000, p.y + 3000))
+        .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{Point, mbc_ch, draw, bridge, MBCTestData};
-    use std::fs::read;
-    use rand::Rng;
-
-    #[test]
-    fn mbc_ch_test() {
-        let points = vec![
-            Point { x: 41, y: -6 },
-            Point { x: -24, y: -74 },
-            Point { x: -51, y: -6 },
-            Point { x: 73, y: 17 },
-            Point { x: -30, y: -34 },
-        ];
-        let mut test_data = MBCTestData::new();
-        assert_eq!(mbc_ch(points, &mut test_data), [Point { x: -51, y: -6 }, Point { x: 73, y: 17 }, Point { x: -24, y: -74 }]);
-    }
-
-    #[test]
-    fn mbc_ch_test2() {
-        let points = vec![
-            Point { x: 4, y: 11 },
-            Point { x: 14, y: 13 },
-            Point { x: 21, y: 10 },
-            Point { x: 18, y: 11 },
-            Point { x: 10, y: 11 },
-            Point { x: 13, y: 6 },
-            Point { x: 7, y: 6 },
-            Point { x: 19, y: 5 },
-            Point { x: 1, y: 7 },
-        ];
-        let mut test_data = MBCTestData::new();
-        println!("{:?}", mbc_ch(points, &mut test_data));
-    }
-
-    #[test]
-    fn bridge_test_two_points() {
-        let points = bridge(&vec![
-            Point { x: 4, y: 11 },
-            Point { x: 14, y: 13 },
-        ], 4, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 14, y: 13 }));
-        let points = bridge(&vec![
-            Point { x: 14, y: 13 },
-            Point { x: 4, y: 11 },
-        ], 4, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 14, y: 13 }));
-    }
-
-    #[test]
-    fn bridge_test_three_points() {
-        let points = bridge(&vec![
-            Point { x: 19, y: 5 },
-            Point { x: 21, y: 10 },
-            Point { x: 13, y: 6 },
-        ], 13, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 13, y: 6 }, Point { x: 21, y: 10 }));
-        let points = bridge(&vec![
-            Point { x: 21, y: 10 },
-            Point { x: 19, y: 5 },
-            Point { x: 13, y: 6 },
-        ], 13, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 13, y: 6 }, Point { x: 21, y: 10 }));
-        let points = bridge(&vec![
-            Point { x: 13, y: 6 },
-            Point { x: 19, y: 5 },
-            Point { x: 21, y: 10 },
-        ], 13, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 13, y: 6 }, Point { x: 21, y: 10 }));
-    }
-
-    #[test]
-    fn bridge_test1() {
-        let points = bridge(&vec![
-            Point { x: 4, y: 11 },
-            Point { x: 14, y: 13 },
-            Point { x: 21, y: 10 },
-            Point { x: 18, y: 11 },
-            Point { x: 10, y: 11 },
-            Point { x: 13, y: 6 },
-            Point { x: 7, y: 6 },
-            Point { x: 19, y: 5 },
-            Point { x: 1, y: 7 },
-        ], 10, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 14, y: 13 }));
-    }
-
-    #[test]
-    fn bridge_test2() {
-        let points = bridge(&vec![
-            Point { x: 4, y: 11 },
-            Point { x: 21, y: 10 },
-            Point { x: 18, y: 11 },
-            Point { x: 10, y: 11 },
-            Point { x: 13, y: 6 },
-            Point { x: 7, y: 6 },
-            Point { x: 19, y: 5 },
-            Point { x: 1, y: 7 },
-        ], 10, &mut MBCTestData::new());
-        assert_eq!(points, (Point { x: 4, y: 11 }, Point { x: 18, y: 11 }));
-    }
-
-    #[test]
-    fn that_damn_float_precision_bug() {
-        let points = vec![
-            Point { x: 804, y: 2271 }, Point { x: -136, y: 2382 }, Point { x: 2686, y: 4952 }, Point { x: -289, y: 3189 }, Point { x: 6560, y: 1644 }
-        ];
-        assert_eq!(bridge(&points, 804, &mut MBCTestData::new()), (Point { x: -289, y: 3189 }, Point { x: 2686, y: 4952 }));
+/// Generates `count` points and removes duplicates.
+/// The points are shuffled by `rng` rather than left in hash order, so a seeded `rng` always
+/// produces the same input in the same order. `ordered` points keep their duplicates and the
+/// order they were generated in, which for polygons is along their boundary.
+fn generate_points(count: i64, strategy: PointGeneratorStrategy, ordered: bool, rng: &mut StdRng) -> Vec<Point> {
+    let mut points: Vec<_> = match strategy {
+        PointGeneratorStrategy::Circle => (0..count).map(|_| generate_circle_point(rng)).collect(),
+        PointGeneratorStrategy::Square => (0..count).map(|_| generate_square_point(rng)).collect(),
+        PointGeneratorStrategy::Exp => (0..count).map(generate_exp_point).collect(),
+        PointGeneratorStrategy::Polygon => generate_polygon(count, rng),
+    };
+    if ordered {
+        return points;
     }
 
-    #[test]
-    fn mbc_perf() {
-        let mut rng = rand::thread_rng();
-        let mut points = std::vec::Vec::<Point>::new();
-
-        for _ in 1..10000 {
-            let a = rng.gen_range(0f64, 360f64);
-            let r = 4000f64 * f64::sqrt(rng.gen_range(0f64, 1f64));
-            let xx = (r * f64::cos(a)) as i64 + 3000;
-            let y = (r * f64::sin(a)) as i64 + 3000;
-
-            points.push(Point::new(xx, y))
-        }
-        let mut test_data = MBCTestData::new();
-        mbc_ch(points, &mut test_data);
-        println!("{:?}", test_data.time_elapsed().as_nanos())
-    }
-}
\ No newline at end of file
+    let mut seen = HashSet::new();
+    points.retain(|p| seen.insert(*p));
+    points.shuffle(rng);
+    points
+}
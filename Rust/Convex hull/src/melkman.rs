@@ -0,0 +1,164 @@
+//! Melkman's algorithm, which finds the hull of a simple polygon or polyline in O(n) by keeping
+//! the hull of the path so far in a deque, with the last point at both ends.
+//! A point that is not right of either edge at the ends is inside the hull and skipped. Otherwise both
+//! ends are popped until the point makes a left turn with them, and it is pushed on both ends.
+//! This only holds for simple paths: a path that crosses itself can enter the hull and leave it
+//! again through another edge.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use rand::Rng;
+use crate::metrics::Metrics;
+use crate::{dir, Point};
+
+/// The hull of a simple polygon or polyline, given as its points in order, in the same order as
+/// `inc_ch` returns hulls. Repeated points and a closing point equal to the first are ignored.
+pub fn melkman_ch(path: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    test_struct.set_start_time();
+    let mut path: Vec<Point> = path.to_vec();
+    path.dedup();
+    if path.len() > 1 && path[0] == path[path.len() - 1] {
+        path.pop();
+    }
+    let mut turn = |a: Point, b: Point, c: Point| {
+        let direction = dir(a, b, c);
+        test_struct.increment_turn(direction);
+        direction
+    };
+
+    // Points collinear with the start of a simple path move away from it along a line, so the
+    // first of them and the last are the ends of a segment that holds them all.
+    let third = match (2..path.len()).find(|&i| dir(path[0], path[1], path[i]) != 0) {
+        Some(i) => i,
+        None => {
+            test_struct.set_end_time();
+            return match (path.iter().min(), path.iter().max()) {
+                (Some(&min), Some(&max)) if min != max => vec![min, max],
+                (Some(&min), _) => vec![min],
+                _ => Vec::new(),
+            };
+        }
+    };
+    let (a, b, c) = (path[0], path[third - 1], path[third]);
+    // The deque holds the hull counter-clockwise from front to back.
+    let mut deque: VecDeque<Point> = if turn(a, b, c) > 0 {
+        VecDeque::from(vec![c, a, b, c])
+    } else {
+        VecDeque::from(vec![c, b, a, c])
+    };
+
+    for &point in &path[third + 1..] {
+        let n = deque.len();
+        // Points on an end edge are on the hull's boundary and skipped like those inside it.
+        if turn(deque[n - 2], deque[n - 1], point) >= 0 && turn(deque[0], deque[1], point) >= 0 {
+            continue;
+        }
+        while turn(deque[deque.len() - 2], deque[deque.len() - 1], point) <= 0 {
+            deque.pop_back();
+        }
+        deque.push_back(point);
+        while turn(point, deque[0], deque[1]) <= 0 {
+            deque.pop_front();
+        }
+        deque.push_front(point);
+    }
+    deque.pop_back();
+
+    // `inc_ch` starts at the smallest point and goes clockwise.
+    let mut hull: Vec<Point> = deque.into_iter().rev().collect();
+    let start = (0..hull.len()).min_by_key(|&i| hull[i]).unwrap();
+    hull.rotate_left(start);
+    test_struct.set_end_time();
+    hull
+}
+
+/// A random simple polygon with up to `count` vertices within `radius` of the origin.
+/// The lowest of some random points is the first vertex and the others follow by their angle
+/// around it, which makes a polygon that is star-shaped from its first vertex. Of points at the
+/// same angle only the farthest is kept, so fewer than `count` vertices may be returned.
+pub fn random_simple_polygon<R: Rng>(count: usize, radius: i64, rng: &mut R) -> Vec<Point> {
+    let mut points: Vec<Point> = (0..count)
+        .map(|_| loop {
+            let point = Point::new(rng.gen_range(-radius, radius + 1), rng.gen_range(-radius, radius + 1));
+            if point.x * point.x + point.y * point.y <= radius * radius {
+                break point;
+            }
+        })
+        .collect();
+    let lowest = match points.iter().min_by_key(|p| (p.y, p.x)) {
+        Some(&lowest) => lowest,
+        None => return points,
+    };
+    points.retain(|&p| p != lowest);
+    // Every other point is above the lowest or right of it, so angles around it are below pi
+    // and compare exactly by the direction of the turn between them.
+    let by_angle = |a: &Point, b: &Point| 0.cmp(&dir(lowest, *a, *b));
+    let distance = |p: &Point| (p.x - lowest.x).pow(2) + (p.y - lowest.y).pow(2);
+    points.sort_by(|a, b| by_angle(a, b).then_with(|| distance(b).cmp(&distance(a))));
+    points.dedup_by(|b, a| by_angle(a, b) == Ordering::Equal);
+    points.insert(0, lowest);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::melkman::{melkman_ch, random_simple_polygon};
+    use crate::metrics::Metrics;
+    use crate::{inc_ch, Point};
+
+    #[test]
+    fn melkman_matches_inc_ch_on_simple_polygons() {
+        let mut rng = rand::thread_rng();
+        for round in 0..300 {
+            let count = if round % 10 == 0 { 2000 } else { 3 + round % 40 };
+            let polygon = random_simple_polygon(count, 50, &mut rng);
+            let mut expected = inc_ch(&polygon, &mut Metrics::new());
+            // inc_ch returns hulls of one or two points in hash order.
+            if expected.len() <= 2 {
+                expected.sort();
+            }
+            assert_eq!(melkman_ch(&polygon, &mut Metrics::new()), expected, "polygon {:?}", polygon);
+            // Every rotation and both directions are the same polygon, closed or not.
+            let mut path = polygon.clone();
+            path.rotate_left(round % polygon.len());
+            path.reverse();
+            path.push(path[0]);
+            assert_eq!(melkman_ch(&path, &mut Metrics::new()), expected, "path {:?}", path);
+        }
+    }
+
+    #[test]
+    fn polylines_and_degenerate_paths() {
+        let zigzag = [Point::new(0, 0), Point::new(1, 3), Point::new(2, 0), Point::new(3, 3), Point::new(4, 0)];
+        assert_eq!(melkman_ch(&zigzag, &mut Metrics::new()), inc_ch(&zigzag, &mut Metrics::new()));
+        let starts_straight = [Point::new(0, 0), Point::new(1, 0), Point::new(2, 0), Point::new(2, 2), Point::new(0, 2)];
+        assert_eq!(melkman_ch(&starts_straight, &mut Metrics::new()), inc_ch(&starts_straight, &mut Metrics::new()));
+        let u_shape = [
+            Point::new(0, 0), Point::new(6, 0), Point::new(6, 6), Point::new(4, 6),
+            Point::new(4, 2), Point::new(2, 2), Point::new(2, 6), Point::new(0, 6),
+        ];
+        for start in 0..u_shape.len() {
+            let mut path = u_shape.to_vec();
+            path.rotate_left(start);
+            assert_eq!(melkman_ch(&path, &mut Metrics::new()), inc_ch(&u_shape, &mut Metrics::new()));
+        }
+        let line = [Point::new(0, 0), Point::new(1, 1), Point::new(1, 1), Point::new(2, 2)];
+        assert_eq!(melkman_ch(&line, &mut Metrics::new()), vec![Point::new(0, 0), Point::new(2, 2)]);
+        let falling: Vec<Point> = (0..5).map(|y| Point::new(2, 4 - y)).collect();
+        assert_eq!(melkman_ch(&falling, &mut Metrics::new()), vec![Point::new(2, 0), Point::new(2, 4)]);
+        let there_and_back = [Point::new(3, 1), Point::new(0, 0), Point::new(3, 1)];
+        assert_eq!(melkman_ch(&there_and_back, &mut Metrics::new()), vec![Point::new(0, 0), Point::new(3, 1)]);
+        assert_eq!(melkman_ch(&[Point::new(1, 1); 5], &mut Metrics::new()), vec![Point::new(1, 1)]);
+        assert_eq!(melkman_ch(&[Point::new(1, 1)], &mut Metrics::new()), vec![Point::new(1, 1)]);
+        assert!(melkman_ch(&[], &mut Metrics::new()).is_empty());
+    }
+
+    #[test]
+    fn random_polygons_are_star_shaped_from_their_first_vertex() {
+        let mut rng = rand::thread_rng();
+        let polygon = random_simple_polygon(500, 100, &mut rng);
+        for pair in polygon[1..].windows(2) {
+            assert!(crate::dir(polygon[0], pair[0], pair[1]) > 0);
+        }
+    }
+}
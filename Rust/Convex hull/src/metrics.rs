@@ -0,0 +1,180 @@
+use std::time::{Duration, Instant};
+
+/// Counters every algorithm reports, in report order.
+/// They have fixed slots so that counting orientation tests stays cheap.
+const TURN_COUNTERS: [&str; 3] = ["right_turns", "left_turns", "no_turns"];
+const RIGHT_TURN: usize = 0;
+const LEFT_TURN: usize = 1;
+const NO_TURN: usize = 2;
+
+/// Named counters, measured values and phase timers an algorithm reports into while it runs.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    time_start: Instant,
+    time_end: Instant,
+    counters: Vec<(&'static str, i64)>,
+    measures: Vec<(&'static str, f64)>,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            time_start: Instant::now(),
+            time_end: Instant::now(),
+            counters: TURN_COUNTERS.iter().map(|&name| (name, 0)).collect(),
+            measures: Vec::new(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Counts an orientation test by the sign of its result, see `dir`.
+    pub fn increment_turn(&mut self, direction: i64) {
+        let slot = if direction == 0 { NO_TURN } else if direction > 0 { RIGHT_TURN } else { LEFT_TURN };
+        self.counters[slot].1 += 1;
+    }
+
+    /// Adds `amount` to the named counter, registering it on first use.
+    pub fn count(&mut self, name: &'static str, amount: i64) {
+        match self.counters.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, value)) => *value += amount,
+            None => self.counters.push((name, amount)),
+        }
+    }
+
+    pub fn counter(&self, name: &str) -> i64 {
+        if name == "total_turns" {
+            return self.total_turns();
+        }
+        self.counters.iter().find(|(n, _)| *n == name).map_or(0, |(_, value)| *value)
+    }
+
+    pub fn total_turns(&self) -> i64 {
+        self.counters[RIGHT_TURN].1 + self.counters[LEFT_TURN].1 + self.counters[NO_TURN].1
+    }
+
+    /// The turn counters and their derived `total_turns`, followed by the named counters in
+    /// registration order.
+    pub fn counters(&self) -> Vec<(&'static str, i64)> {
+        let mut counters = self.counters.clone();
+        counters.insert(TURN_COUNTERS.len(), ("total_turns", self.total_turns()));
+        counters
+    }
+
+    /// Sets the named measure, a value that isn't a count, like a distance. Unlike counters,
+    /// measures are set rather than added to, and aren't merged.
+    pub fn measure(&mut self, name: &'static str, value: f64) {
+        match self.measures.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, measure)) => *measure = value,
+            None => self.measures.push((name, value)),
+        }
+    }
+
+    /// The measures in the order they were first set.
+    pub fn measures(&self) -> &[(&'static str, f64)] {
+        &self.measures
+    }
+
+    /// Adds every counter of `other`, like the metrics of work done on another thread.
+    /// Times and phases are left alone, since work on other threads overlaps.
+    pub fn merge_counters(&mut self, other: &Metrics) {
+        for &(name, value) in &other.counters {
+            self.count(name, value);
+        }
+    }
+
+    pub fn set_start_time(&mut self) { self.time_start = Instant::now() }
+    pub fn set_end_time(&mut self) { self.time_end = Instant::now() }
+    pub fn time_elapsed(&self) -> Duration { self.time_end.duration_since(self.time_start) }
+
+    /// Adds the time since `start` to the named phase.
+    /// Phases that run several times, like the subhulls of each round of Chan's algorithm,
+    /// accumulate their time under one name. Phases are kept in the order they first ran.
+    pub fn end_phase(&mut self, name: &'static str, start: Instant) {
+        let elapsed = start.elapsed();
+        match self.phases.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, time)) => *time += elapsed,
+            None => self.phases.push((name, elapsed)),
+        }
+    }
+
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::Metrics;
+    use std::time::Instant;
+
+    #[test]
+    fn turns_and_named_counters() {
+        let mut metrics = Metrics::new();
+        metrics.increment_turn(5);
+        metrics.increment_turn(-1);
+        metrics.increment_turn(-7);
+        metrics.increment_turn(0);
+        metrics.count("bridges", 2);
+        metrics.count("recursions", 1);
+        metrics.count("bridges", 1);
+        assert_eq!(metrics.counters(), vec![
+            ("right_turns", 1),
+            ("left_turns", 2),
+            ("no_turns", 1),
+            ("total_turns", 4),
+            ("bridges", 3),
+            ("recursions", 1),
+        ]);
+        assert_eq!(metrics.counter("bridges"), 3);
+        assert_eq!(metrics.counter("total_turns"), 4);
+        assert_eq!(metrics.counter("unknown"), 0);
+    }
+
+    #[test]
+    fn merged_counters_add_up() {
+        let mut metrics = Metrics::new();
+        metrics.increment_turn(-1);
+        metrics.count("chunks", 1);
+        let mut other = Metrics::new();
+        other.increment_turn(-2);
+        other.increment_turn(3);
+        other.count("bridges", 4);
+        metrics.merge_counters(&other);
+        assert_eq!(metrics.counters(), vec![
+            ("right_turns", 1),
+            ("left_turns", 2),
+            ("no_turns", 0),
+            ("total_turns", 3),
+            ("chunks", 1),
+            ("bridges", 4),
+        ]);
+    }
+
+    #[test]
+    fn repeated_phases_accumulate() {
+        let mut metrics = Metrics::new();
+        metrics.end_phase("sort", Instant::now());
+        metrics.end_phase("wrap", Instant::now());
+        metrics.end_phase("sort", Instant::now());
+        let names: Vec<_> = metrics.phases().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["sort", "wrap"]);
+    }
+
+    #[test]
+    fn measures_are_set_and_kept_apart() {
+        let mut metrics = Metrics::new();
+        metrics.measure("error", 0.25);
+        metrics.measure("error_bound", 1.5);
+        metrics.measure("error", 0.75);
+        assert_eq!(metrics.measures(), &[("error", 0.75), ("error_bound", 1.5)]);
+        assert_eq!(metrics.counter("error"), 0);
+        assert_eq!(metrics.counters().len(), 4);
+    }
+}
@@ -0,0 +1,129 @@
+//! A hull of a stream of points that arrive one at a time, in any order.
+//! Only the upper and lower hull chains are kept, in ordered sets, so memory is O(h) however
+//! many points arrive. A point inside the hull costs two lookups; a point outside is inserted
+//! and pops the neighbours it hides, and every point is popped at most once, so an insert costs
+//! O(log h) amortised.
+
+use std::collections::BTreeSet;
+use std::ops::Bound::{Excluded, Unbounded};
+use crate::dynamic::outside;
+use crate::metrics::Metrics;
+use crate::Point;
+
+#[derive(Debug, Clone, Default)]
+pub struct OnlineHull {
+    /// The chain `inc_ch` returns first.
+    upper: BTreeSet<Point>,
+    lower: BTreeSet<Point>,
+    points_seen: usize,
+    metrics: Metrics,
+}
+
+impl OnlineHull {
+    pub fn new() -> Self {
+        OnlineHull::default()
+    }
+
+    /// Adds a point, returning whether the hull changed.
+    pub fn insert(&mut self, point: Point) -> bool {
+        self.points_seen += 1;
+        let upper_changed = insert_into_chain(&mut self.upper, point, true, &mut self.metrics);
+        let lower_changed = insert_into_chain(&mut self.lower, point, false, &mut self.metrics);
+        upper_changed || lower_changed
+    }
+
+    /// The amount of points inserted, including those inside the hull and repeated ones.
+    pub fn points_seen(&self) -> usize {
+        self.points_seen
+    }
+
+    /// The turns counted by every insert so far.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// The hull in the same order as `inc_ch` returns it.
+    pub fn hull(&self) -> Vec<Point> {
+        let mut hull: Vec<Point> = self.upper.iter().copied().collect();
+        if self.lower.len() > 2 {
+            hull.extend(self.lower.iter().rev().skip(1).take(self.lower.len() - 2));
+        }
+        hull
+    }
+}
+
+impl Extend<Point> for OnlineHull {
+    fn extend<T: IntoIterator<Item = Point>>(&mut self, points: T) {
+        for point in points {
+            self.insert(point);
+        }
+    }
+}
+
+fn insert_into_chain(chain: &mut BTreeSet<Point>, point: Point, upper: bool, metrics: &mut Metrics) -> bool {
+    if chain.contains(&point) {
+        return false;
+    }
+    let before = chain.range(..point).next_back().copied();
+    let after = chain.range((Excluded(point), Unbounded)).next().copied();
+    if let (Some(a), Some(b)) = (before, after) {
+        if !outside(a, b, point, upper, metrics) {
+            return false;
+        }
+    }
+    chain.insert(point);
+
+    loop {
+        let mut left = chain.range(..point).rev();
+        let (b, a) = match (left.next(), left.next()) {
+            (Some(&b), Some(&a)) => (b, a),
+            _ => break,
+        };
+        if outside(a, point, b, upper, metrics) {
+            break;
+        }
+        chain.remove(&b);
+    }
+    loop {
+        let mut right = chain.range((Excluded(point), Unbounded));
+        let (b, c) = match (right.next(), right.next()) {
+            (Some(&b), Some(&c)) => (b, c),
+            _ => break,
+        };
+        if outside(point, c, b, upper, metrics) {
+            break;
+        }
+        chain.remove(&b);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::Metrics;
+    use crate::online::OnlineHull;
+    use crate::{inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn online_hull_matches_inc_ch_after_every_point() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let mut hull = OnlineHull::new();
+            let mut points = Vec::new();
+            for _ in 0..100 {
+                let point = Point::new(rng.gen_range(0, 30), rng.gen_range(0, 30));
+                points.push(point);
+                let changed = hull.insert(point);
+                let expected = inc_ch(&points, &mut Metrics::new());
+                if expected.len() >= 3 {
+                    assert_eq!(hull.hull(), expected, "hull of {:?}", points);
+                }
+                if !changed {
+                    assert!(points[..points.len() - 1].contains(&point) || expected.iter().all(|&p| p != point));
+                }
+            }
+            assert_eq!(hull.points_seen(), 100);
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Operations on two hulls: their intersection, the hull of their union and their Minkowski sum.
+//! Each walks the boundaries of both hulls once, so it takes time linear in their sizes. The
+//! walks decide everything with exact turns of the integer vertices, and only the crossings of
+//! edges in an intersection are computed in floating point.
+
+use crate::hull::Hull;
+use crate::query::{clockwise_before, HullIndex, Location};
+use crate::{dir, Point};
+
+/// Which of the two polygons the boundary of their intersection currently follows.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Inside {
+    Unknown,
+    First,
+    Second,
+}
+
+/// How two segments meet.
+enum Meeting {
+    Apart,
+    /// In one point, which can be an end of either.
+    Point((f64, f64)),
+    /// Along a part of both, from one point to the other, when they are on one line.
+    Overlap((f64, f64), (f64, f64)),
+}
+
+/// The intersection of two hulls, a convex polygon clockwise from its smallest vertex, by the
+/// algorithm of O'Rourke, Chien, Olson and Naddor. It advances around both hulls at once, on the
+/// edge that points towards the other, and the boundary of the intersection switches hulls where
+/// the edges cross. The crossings are usually not integer points. Hulls that only touch meet in a
+/// point or a segment, and hulls that don't meet have an empty intersection. When the boundaries
+/// don't cross, whether one hull is inside the other takes a point location per vertex.
+pub fn intersection(first: &Hull, second: &Hull) -> Vec<(f64, f64)> {
+    if first.len() < 3 || second.len() < 3 {
+        return degenerate_intersection(first, second);
+    }
+    // The algorithm goes around counter-clockwise, with the inside of each edge to its left.
+    let p: Vec<Point> = first.vertices().iter().rev().copied().collect();
+    let q: Vec<Point> = second.vertices().iter().rev().copied().collect();
+    let (n, m) = (p.len(), q.len());
+
+    let (mut i, mut j) = (0, 0);
+    let (mut advanced_i, mut advanced_j) = (0, 0);
+    let mut inside = Inside::Unknown;
+    let mut crossed = false;
+    let mut boundary: Vec<(f64, f64)> = Vec::new();
+    loop {
+        let (i1, j1) = ((i + n - 1) % n, (j + m - 1) % m);
+        let (a, b) = (edge(p[i1], p[i]), edge(q[j1], q[j]));
+        let cross = (a.0 * b.1 - a.1 * b.0).signum();
+        // Whether the heads of the edges are inside the edge of the other hull.
+        let head_in_second = dir(q[j1], q[j], p[i]).signum();
+        let head_in_first = dir(p[i1], p[i], q[j]).signum();
+
+        match meet(p[i1], p[i], q[j1], q[j]) {
+            Meeting::Point(point) => {
+                // The walk is counted from the first crossing, so it goes around once after it.
+                if inside == Inside::Unknown && !crossed {
+                    advanced_i = 0;
+                    advanced_j = 0;
+                    crossed = true;
+                }
+                boundary.push(point);
+                if head_in_second > 0 {
+                    inside = Inside::First;
+                } else if head_in_first > 0 {
+                    inside = Inside::Second;
+                }
+            }
+            // Edges along one line in opposite directions have the hulls on either side of it.
+            Meeting::Overlap(from, to) if a.0 * b.0 + a.1 * b.1 < 0 => return normalize(vec![from, to]),
+            _ => {}
+        }
+
+        if cross == 0 && head_in_second < 0 && head_in_first < 0 {
+            // Parallel edges with each hull outside the other's.
+            return Vec::new();
+        }
+        let advance_first = if cross == 0 && head_in_second == 0 && head_in_first == 0 {
+            inside != Inside::First
+        } else if cross >= 0 {
+            head_in_first > 0
+        } else {
+            head_in_second <= 0
+        };
+        if advance_first {
+            if inside == Inside::First {
+                boundary.push(exact(p[i]));
+            }
+            advanced_i += 1;
+            i = (i + 1) % n;
+        } else {
+            if inside == Inside::Second {
+                boundary.push(exact(q[j]));
+            }
+            advanced_j += 1;
+            j = (j + 1) % m;
+        }
+        if !((advanced_i < n || advanced_j < m) && advanced_i < 2 * n && advanced_j < 2 * m) {
+            break;
+        }
+    }
+
+    if inside == Inside::Unknown {
+        // The boundaries don't cross, so one hull is in the other, or they touch or are apart.
+        let (first_index, second_index) = (HullIndex::new(first), HullIndex::new(second));
+        if first.vertices().iter().all(|&v| second_index.contains(v) != Location::Outside) {
+            return first.vertices().iter().map(|&v| exact(v)).collect();
+        }
+        if second.vertices().iter().all(|&v| first_index.contains(v) != Location::Outside) {
+            return second.vertices().iter().map(|&v| exact(v)).collect();
+        }
+    }
+    normalize(boundary)
+}
+
+/// The intersection when a hull is a point or a segment, which is a part of it.
+fn degenerate_intersection(first: &Hull, second: &Hull) -> Vec<(f64, f64)> {
+    let (small, large) = if first.len() <= second.len() { (first, second) } else { (second, first) };
+    let index = HullIndex::new(large);
+    match *small.vertices() {
+        [] => Vec::new(),
+        [point] if index.contains(point) != Location::Outside => vec![exact(point)],
+        [_] => Vec::new(),
+        [a, b, ..] => {
+            // The part of the line through the segment that is in the other hull, cut to the segment.
+            let along = |p: (f64, f64)| (b.x - a.x) as f64 * (p.0 - a.x as f64) + (b.y - a.y) as f64 * (p.1 - a.y as f64);
+            let length = along(exact(b));
+            match index.line_intersection((a, b)) {
+                Some((entry, exit)) if along(entry) <= length && along(exit) >= 0.0 => {
+                    let entry = if along(entry) < 0.0 { exact(a) } else { entry };
+                    let exit = if along(exit) > length { exact(b) } else { exit };
+                    normalize(vec![entry, exit])
+                }
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+/// Where the segments from `a` to `b` and from `c` to `d` meet. Ends are returned exactly.
+fn meet(a: Point, b: Point, c: Point, d: Point) -> Meeting {
+    let (ab, cd, ac) = (edge(a, b), edge(c, d), edge(a, c));
+    let denominator = ab.0 * cd.1 - ab.1 * cd.0;
+    if denominator == 0 {
+        if dir(a, b, c) != 0 {
+            return Meeting::Apart;
+        }
+        // Both are on one line, where they overlap between the larger start and the smaller end.
+        let along = |p: Point| ab.0 * (p.x - a.x) as i128 + ab.1 * (p.y - a.y) as i128;
+        let (c_end, d_end) = if along(c) <= along(d) { (c, d) } else { (d, c) };
+        let from = if along(c_end) > 0 { c_end } else { a };
+        let to = if along(d_end) < along(b) { d_end } else { b };
+        if along(from) > along(to) {
+            return Meeting::Apart;
+        }
+        return Meeting::Overlap(exact(from), exact(to));
+    }
+    // The meeting point is a + s (b - a) = c + t (d - c), with s and t between 0 and 1.
+    let sign = denominator.signum();
+    let (denominator, s, t) = (denominator * sign, (ac.0 * cd.1 - ac.1 * cd.0) * sign, (ac.0 * ab.1 - ac.1 * ab.0) * sign);
+    if s < 0 || s > denominator || t < 0 || t > denominator {
+        return Meeting::Apart;
+    }
+    Meeting::Point(if s == 0 {
+        exact(a)
+    } else if s == denominator {
+        exact(b)
+    } else if t == 0 {
+        exact(c)
+    } else if t == denominator {
+        exact(d)
+    } else {
+        let s = s as f64 / denominator as f64;
+        (a.x as f64 + s * ab.0 as f64, a.y as f64 + s * ab.1 as f64)
+    })
+}
+
+fn edge(from: Point, to: Point) -> (i128, i128) {
+    ((to.x - from.x) as i128, (to.y - from.y) as i128)
+}
+
+fn exact(point: Point) -> (f64, f64) {
+    (point.x as f64, point.y as f64)
+}
+
+/// The boundary without repeated points, clockwise from its smallest point.
+fn normalize(mut boundary: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    boundary.dedup();
+    while boundary.len() > 1 && boundary[0] == boundary[boundary.len() - 1] {
+        boundary.pop();
+    }
+    // Counter-clockwise boundaries of the walk go the other way around.
+    if boundary.len() >= 3 {
+        boundary.reverse();
+    }
+    let start = (0..boundary.len())
+        .min_by(|&i, &j| boundary[i].partial_cmp(&boundary[j]).unwrap())
+        .unwrap_or(0);
+    boundary.rotate_left(start);
+    boundary
+}
+
+/// The hull of the points of both hulls. The vertices of a hull are sorted by merging its upper
+/// chain with its lower chain reversed, the vertices of both by merging those, and a monotone
+/// chain over them is linear, without sorting.
+pub fn union_hull(first: &Hull, second: &Hull) -> Hull {
+    let mut points = merge(&sorted_vertices(first), &sorted_vertices(second));
+    points.dedup();
+    if points.len() <= 2 {
+        return Hull::new(points);
+    }
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len());
+    // The upper chain from the smallest point to the largest and the lower chain back, both
+    // keeping only right turns, like `inc_ch`.
+    for &point in &points {
+        while hull.len() >= 2 && dir(hull[hull.len() - 2], hull[hull.len() - 1], point) >= 0 {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    let upper = hull.len();
+    for &point in points.iter().rev().skip(1) {
+        while hull.len() > upper && dir(hull[hull.len() - 2], hull[hull.len() - 1], point) >= 0 {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    hull.pop();
+    Hull::new(hull)
+}
+
+/// The vertices of a hull in ascending order.
+fn sorted_vertices(hull: &Hull) -> Vec<Point> {
+    let v = hull.vertices();
+    // Going clockwise from the smallest vertex, the upper chain ascends to the largest.
+    let largest = match (0..v.len()).max_by_key(|&i| v[i]) {
+        Some(largest) => largest,
+        None => return Vec::new(),
+    };
+    let lower: Vec<Point> = v[largest + 1..].iter().rev().copied().collect();
+    merge(&v[..=largest], &lower)
+}
+
+fn merge(a: &[Point], b: &[Point]) -> Vec<Point> {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] <= b[j] {
+            merged.push(a[i]);
+            i += 1;
+        } else {
+            merged.push(b[j]);
+            j += 1;
+        }
+    }
+    merged.extend_from_slice(&a[i..]);
+    merged.extend_from_slice(&b[j..]);
+    merged
+}
+
+/// The Minkowski sum of two hulls, the points `a + b` for `a` in one and `b` in the other.
+/// Its smallest vertex is the sum of their smallest vertices, and its edges are the edges of
+/// both, merged in the order they turn in going clockwise.
+pub fn minkowski_sum(first: &Hull, second: &Hull) -> Hull {
+    let (a, b) = (first.vertices(), second.vertices());
+    if a.is_empty() || b.is_empty() {
+        return Hull::new(Vec::new());
+    }
+    let edges = |v: &[Point]| -> Vec<(i128, i128)> {
+        if v.len() < 2 {
+            return Vec::new();
+        }
+        (0..v.len()).map(|i| edge(v[i], v[(i + 1) % v.len()])).collect()
+    };
+    let (a_edges, b_edges) = (edges(a), edges(b));
+    // Going clockwise from the smallest vertex, the first edge points up or to the right, so the
+    // edges of each hull turn clockwise from straight up.
+    let up = (0, 1);
+    let mut sum = vec![Point::new(a[0].x + b[0].x, a[0].y + b[0].y)];
+    let (mut i, mut j) = (0, 0);
+    while i < a_edges.len() || j < b_edges.len() {
+        let next = if j == b_edges.len() || (i < a_edges.len() && !clockwise_before(up, b_edges[j], a_edges[i])) {
+            i += 1;
+            a_edges[i - 1]
+        } else {
+            j += 1;
+            b_edges[j - 1]
+        };
+        let last = sum[sum.len() - 1];
+        sum.push(Point::new(last.x + next.0 as i64, last.y + next.1 as i64));
+    }
+    Hull::new(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hull::Hull;
+    use crate::metrics::Metrics;
+    use crate::operations::{intersection, minkowski_sum, union_hull};
+    use crate::{inc_ch, Point};
+    use rand::Rng;
+
+    fn random_hull(count: usize, offset: i64, range: i64) -> Hull {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..count).map(|_| Point::new(offset + rng.gen_range(0, range), offset + rng.gen_range(0, range))).collect();
+        Hull::new(inc_ch(&points, &mut Metrics::new()))
+    }
+
+    fn area(polygon: &[(f64, f64)]) -> f64 {
+        let n = polygon.len();
+        (0..n).map(|i| polygon[i].0 * polygon[(i + 1) % n].1 - polygon[(i + 1) % n].0 * polygon[i].1).sum::<f64>().abs() / 2.0
+    }
+
+    /// The intersection by clipping the first polygon with every edge of the second.
+    fn clip(first: &Hull, second: &Hull) -> Vec<(f64, f64)> {
+        let mut polygon: Vec<(f64, f64)> = first.vertices().iter().map(|p| (p.x as f64, p.y as f64)).collect();
+        let v = second.vertices();
+        for i in 0..v.len() {
+            let (a, b) = (v[i], v[(i + 1) % v.len()]);
+            // The inside of a clockwise edge is to its right.
+            let side = |p: (f64, f64)| (b.x - a.x) as f64 * (p.1 - a.y as f64) - (b.y - a.y) as f64 * (p.0 - a.x as f64);
+            let mut clipped = Vec::new();
+            for k in 0..polygon.len() {
+                let (p, q) = (polygon[k], polygon[(k + 1) % polygon.len()]);
+                if side(p) <= 0.0 {
+                    clipped.push(p);
+                }
+                if side(p) * side(q) < 0.0 {
+                    let t = side(p) / (side(p) - side(q));
+                    clipped.push((p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)));
+                }
+            }
+            polygon = clipped;
+        }
+        polygon
+    }
+
+    #[test]
+    fn intersection_matches_clipping() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(3, 4), (5, 6), (20, 10), (100, 100), (1_000, 1_000_000)].iter() {
+            for _ in 0..300 {
+                let first = random_hull(count, 0, range);
+                let second = random_hull(count, rng.gen_range(-range / 2, range / 2 + 1), range);
+                if first.len() < 3 || second.len() < 3 {
+                    continue;
+                }
+                let found = intersection(&first, &second);
+                let expected = clip(&first, &second);
+                let tolerance = 1e-9 * (range * range) as f64;
+                assert!((area(&found) - area(&expected)).abs() <= tolerance, "{:?} for {:?} and {:?}", found, first, second);
+                // Every vertex is in both hulls, and consecutive ones differ.
+                for (k, &(x, y)) in found.iter().enumerate() {
+                    for hull in [&first, &second].iter() {
+                        let v = hull.vertices();
+                        for i in 0..v.len() {
+                            let (a, b) = (v[i], v[(i + 1) % v.len()]);
+                            let side = (b.x - a.x) as f64 * (y - a.y as f64) - (b.y - a.y) as f64 * (x - a.x as f64);
+                            assert!(side <= tolerance, "{:?} of {:?} for {:?} and {:?}", (x, y), found, first, second);
+                        }
+                    }
+                    assert!(found.len() == 1 || found[k] != found[(k + 1) % found.len()], "{:?}", found);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn intersections_of_squares() {
+        let square = |x: i64, y: i64, side: i64| Hull::new(vec![Point::new(x, y), Point::new(x, y + side), Point::new(x + side, y + side), Point::new(x + side, y)]);
+        assert_eq!(intersection(&square(0, 0, 4), &square(2, 2, 4)), vec![(2.0, 2.0), (2.0, 4.0), (4.0, 4.0), (4.0, 2.0)]);
+        assert_eq!(intersection(&square(0, 0, 4), &square(1, 1, 2)), vec![(1.0, 1.0), (1.0, 3.0), (3.0, 3.0), (3.0, 1.0)]);
+        assert_eq!(intersection(&square(0, 0, 4), &square(0, 0, 4)), vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (4.0, 0.0)]);
+        assert_eq!(intersection(&square(0, 0, 4), &square(4, 4, 4)), vec![(4.0, 4.0)]);
+        assert_eq!(intersection(&square(0, 0, 4), &square(4, 2, 4)), vec![(4.0, 2.0), (4.0, 4.0)]);
+        assert_eq!(intersection(&square(0, 0, 4), &square(5, 0, 4)), vec![]);
+        // A diamond through the middles of the sides of a square, whose corners stick out.
+        let diamond = Hull::new(vec![Point::new(-1, 2), Point::new(2, 5), Point::new(5, 2), Point::new(2, -1)]);
+        assert_eq!(intersection(&square(0, 0, 4), &diamond),
+                   vec![(0.0, 1.0), (0.0, 3.0), (1.0, 4.0), (3.0, 4.0), (4.0, 3.0), (4.0, 1.0), (3.0, 0.0), (1.0, 0.0)]);
+        let segment = Hull::new(vec![Point::new(-2, 1), Point::new(6, 5)]);
+        assert_eq!(intersection(&segment, &square(0, 0, 4)), vec![(0.0, 2.0), (4.0, 4.0)]);
+        assert_eq!(intersection(&square(0, 0, 4), &Hull::new(vec![Point::new(1, 1)])), vec![(1.0, 1.0)]);
+    }
+
+    #[test]
+    fn union_and_sum_match_hulls_of_points() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(1, 4), (2, 4), (3, 4), (20, 10), (100, 100), (1_000, 1_000_000)].iter() {
+            for _ in 0..100 {
+                let first = random_hull(count, 0, range);
+                let second = random_hull(rng.gen_range(1, count + 2), rng.gen_range(-range, range + 1), range);
+                let both: Vec<Point> = first.vertices().iter().chain(second.vertices()).copied().collect();
+                assert_eq!(union_hull(&first, &second), hull_of(&both), "{:?} and {:?}", first, second);
+                let sums: Vec<Point> = first.vertices().iter()
+                    .flat_map(|a| second.vertices().iter().map(move |b| Point::new(a.x + b.x, a.y + b.y)))
+                    .collect();
+                assert_eq!(minkowski_sum(&first, &second), hull_of(&sums), "{:?} and {:?}", first, second);
+            }
+        }
+    }
+
+    /// The hull of the points, with hulls of one or two points in order.
+    fn hull_of(points: &[Point]) -> Hull {
+        let mut hull = inc_ch(points, &mut Metrics::new());
+        if hull.len() <= 2 {
+            hull.sort();
+            hull.dedup();
+        }
+        Hull::new(hull)
+    }
+}
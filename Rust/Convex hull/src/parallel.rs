@@ -0,0 +1,67 @@
+//! A multi-threaded hull on top of rayon.
+//! The points are split into one chunk per thread, the hull of every chunk is computed in parallel
+//! with the incremental algorithm, and the hull of the union of the chunk hulls is the result.
+//! Only hull points survive the first step, so the merge is cheap unless most points are extreme.
+
+use std::time::Instant;
+use rayon::prelude::*;
+use crate::metrics::Metrics;
+use crate::{inc_ch, Point};
+
+/// Chunks smaller than this aren't worth the cost of handing to another thread.
+const MIN_CHUNK_SIZE: usize = 4096;
+
+/// The parallel hull, in the same order as `inc_ch` returns it.
+/// It runs on the current rayon thread pool, so `ThreadPool::install` controls the thread count.
+/// The turns of every chunk are added to `test_struct`.
+pub fn par_ch(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    test_struct.set_start_time();
+    let chunk_size = usize::max(MIN_CHUNK_SIZE, points.len().div_ceil(rayon::current_num_threads()));
+
+    let phase = Instant::now();
+    let chunks: Vec<(Vec<Point>, Metrics)> = points
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let mut metrics = Metrics::new();
+            let hull = inc_ch(chunk, &mut metrics);
+            (hull, metrics)
+        })
+        .collect();
+    test_struct.end_phase("chunk hulls", phase);
+
+    let phase = Instant::now();
+    let mut candidates = Vec::with_capacity(chunks.iter().map(|(hull, _)| hull.len()).sum());
+    for (hull, metrics) in &chunks {
+        candidates.extend_from_slice(hull);
+        test_struct.merge_counters(metrics);
+    }
+    let mut metrics = Metrics::new();
+    let hull = inc_ch(&candidates, &mut metrics);
+    test_struct.merge_counters(&metrics);
+    test_struct.end_phase("merge", phase);
+
+    test_struct.count("chunks", chunks.len() as i64);
+    test_struct.set_end_time();
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::Metrics;
+    use crate::parallel::par_ch;
+    use crate::{inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn par_ch_matches_inc_ch() {
+        let mut rng = rand::thread_rng();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        for &count in [3, 100, 10_000, 50_000].iter() {
+            let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, 3600), rng.gen_range(0, 3600))).collect();
+            let expected = inc_ch(&points, &mut Metrics::new());
+            let mut metrics = Metrics::new();
+            assert_eq!(pool.install(|| par_ch(&points, &mut metrics)), expected);
+            assert!(metrics.total_turns() > 0);
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Queries against a fixed hull in O(log h) each: point location, extreme points, tangents and
+//! intersections with lines.
+//! The diagonals from the first vertex cut the hull into a fan of triangles. Their directions
+//! turn monotonically around that vertex, so a binary search finds the triangle a point could be
+//! in, and one more turn against the hull edge of that triangle decides where the point is.
+//! The edge directions turn monotonically as well, which is what the other queries search over.
+
+use rayon::prelude::*;
+use crate::hull::Hull;
+use crate::{dir, Point};
+
+/// Where a point is relative to a hull.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+/// A hull prepared for locating many points against it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HullIndex {
+    /// The vertices clockwise from the smallest, like `inc_ch` returns hulls.
+    vertices: Vec<Point>,
+}
+
+impl HullIndex {
+    pub fn new(hull: &Hull) -> Self {
+        HullIndex { vertices: hull.vertices().to_vec() }
+    }
+
+    /// Where the point is, with a binary search over the fan of triangles around the first vertex.
+    pub fn contains(&self, point: Point) -> Location {
+        let v = &self.vertices;
+        match v.len() {
+            0 => Location::Outside,
+            1 => if point == v[0] { Location::Boundary } else { Location::Outside },
+            // Points on a line are ordered along it, so a segment contains what is between its ends.
+            2 => {
+                let (low, high) = (Point::min(v[0], v[1]), Point::max(v[0], v[1]));
+                if dir(low, high, point) == 0 && low <= point && point <= high {
+                    Location::Boundary
+                } else {
+                    Location::Outside
+                }
+            }
+            // Point location is left uncounted, so queries from many threads need no metrics.
+            _ => self.locate(point, &mut |_| ()).0,
+        }
+    }
+
+    /// Where the point is, with an edge of a hull of at least three vertices, by the index of the
+    /// vertex it starts at. A point outside is outside that edge, and one on the boundary is on it.
+    /// Every orientation test is passed to `turns`.
+    fn locate(&self, point: Point, turns: &mut impl FnMut(i64)) -> (Location, usize) {
+        let v = &self.vertices;
+        let n = v.len();
+        // The hull is right of its clockwise edges, so it is between the first edge and the last.
+        let first = turn(v[0], v[1], point, turns);
+        let last = turn(v[0], v[n - 1], point, turns);
+        if first > 0 {
+            return (Location::Outside, 0);
+        }
+        if last < 0 {
+            return (Location::Outside, n - 1);
+        }
+        // The diagonals turn clockwise, and the point is clockwise of, or on, those up to its triangle.
+        let i = v[1..n - 1].partition_point(|&corner| turn(v[0], corner, point, turns) <= 0).max(1);
+        let edge = turn(v[i], v[i + 1], point, turns);
+        if edge > 0 {
+            (Location::Outside, i)
+        } else if edge == 0 {
+            (Location::Boundary, i)
+        } else if first == 0 {
+            (Location::Boundary, 0)
+        } else if last == 0 {
+            (Location::Boundary, n - 1)
+        } else {
+            (Location::Inside, i)
+        }
+    }
+
+    /// The vertex farthest in the direction, that is with the largest dot product with it.
+    /// If an edge is perpendicular to the direction, it is the vertex that edge starts at going
+    /// clockwise. Every vertex is extreme for the zero direction, and the first is returned.
+    pub fn extreme_in_direction(&self, direction: (i64, i64)) -> Option<Point> {
+        self.extreme_in_direction_counted(direction, &mut |_| ())
+    }
+
+    /// `extreme_in_direction`, with the comparison of every edge with the direction passed to
+    /// `turns` as an orientation test.
+    pub(crate) fn extreme_in_direction_counted(&self, direction: (i64, i64), turns: &mut impl FnMut(i64)) -> Option<Point> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+        Some(self.vertices[self.extreme(direction, turns)])
+    }
+
+    /// The index of the extreme vertex in the direction, of a hull with at least one vertex.
+    /// The vertex is the one where the edges turn past the direction turned clockwise by a right
+    /// angle, which the edges before it turn towards from the first edge. Each comparison of an
+    /// edge with that direction is an orientation test, which is passed to `turns`.
+    fn extreme(&self, direction: (i64, i64), turns: &mut impl FnMut(i64)) -> usize {
+        let v = &self.vertices;
+        let n = v.len();
+        let dot = |p: Point| direction.0 as i128 * p.x as i128 + direction.1 as i128 * p.y as i128;
+        if n < 3 || direction == (0, 0) {
+            return (0..n).fold(0, |best, i| if dot(v[i]) > dot(v[best]) { i } else { best });
+        }
+        let edge = |i: usize| {
+            let (a, b) = (v[i], v[(i + 1) % n]);
+            ((b.x - a.x) as i128, (b.y - a.y) as i128)
+        };
+        let turned = (direction.1 as i128, -(direction.0 as i128));
+        let first = edge(0);
+        partition(n, |i| {
+            let edge = edge(i);
+            turns((edge.0 * turned.1 - edge.1 * turned.0).signum() as i64);
+            clockwise_before(first, edge, turned)
+        }) % n
+    }
+
+    /// The vertices where the lines from a point outside the hull touch it, as (left, right): the
+    /// hull is left of the line from the point through the first, and right of the line through
+    /// the second. Of two vertices on such a line the farther is taken, like in gift wrapping.
+    /// For a point on the boundary they are the vertices before and after it going clockwise,
+    /// and a point inside has none.
+    pub fn tangents_from(&self, point: Point) -> Option<(Point, Point)> {
+        self.tangents_from_counted(point, &mut |_| ())
+    }
+
+    /// `tangents_from`, with its O(log h) orientation tests passed to `turns`.
+    pub(crate) fn tangents_from_counted(&self, point: Point, turns: &mut impl FnMut(i64)) -> Option<(Point, Point)> {
+        let v = &self.vertices;
+        let n = v.len();
+        match n {
+            0 => return None,
+            1 => return if point == v[0] { None } else { Some((v[0], v[0])) },
+            2 => {
+                let direction = turn(point, v[0], v[1], turns);
+                return Some(if direction > 0 {
+                    (v[0], v[1])
+                } else if direction < 0 {
+                    (v[1], v[0])
+                } else {
+                    let farther = if distance_squared(point, v[0]) > distance_squared(point, v[1]) { v[0] } else { v[1] };
+                    (farther, farther)
+                });
+            }
+            _ => {}
+        }
+        let next = |i: usize| (i + 1) % n;
+        let previous = |i: usize| (i + n - 1) % n;
+
+        let (location, seen) = self.locate(point, turns);
+        match location {
+            Location::Inside => return None,
+            Location::Boundary => {
+                let (a, b) = (seen, next(seen));
+                return Some(if point == v[a] {
+                    (v[previous(a)], v[b])
+                } else if point == v[b] {
+                    (v[a], v[next(b)])
+                } else {
+                    (v[a], v[b])
+                });
+            }
+            Location::Outside => {}
+        }
+        // The point sees one run of edges, which starts at the left tangent and ends at the right.
+        // The extreme vertex away from the point is on the far side, next to an edge it doesn't see.
+        let far = self.extreme((v[0].x - point.x, v[0].y - point.y), turns);
+        let mut visible = |i: usize| turn(v[i], v[next(i)], point, turns) > 0;
+        let hidden = if visible(far) { previous(far) } else { far };
+        let right = self.first_after(seen, hidden, |i| !visible(i));
+        let left = self.first_after(hidden, seen, visible);
+        // A point on the line through an edge sees both ends of it on one line.
+        let right = if turn(v[right], v[next(right)], point, turns) == 0 { next(right) } else { right };
+        let left = if turn(v[previous(left)], v[left], point, turns) == 0 { previous(left) } else { left };
+        Some((v[left], v[right]))
+    }
+
+    /// Where the line through two distinct points goes into the hull and out of it, in the
+    /// direction from the first point to the second. A line that only touches the hull at a
+    /// vertex goes in and out at the same point.
+    pub fn line_intersection(&self, line: (Point, Point)) -> Option<((f64, f64), (f64, f64))> {
+        let (a, b) = line;
+        assert!(a != b, "A line needs two distinct points");
+        let v = &self.vertices;
+        let n = v.len();
+        let side = |p: Point| dir(a, b, p);
+        let along = |p: (f64, f64)| (b.x - a.x) as f64 * p.0 + (b.y - a.y) as f64 * p.1;
+        let exact = |p: Point| (p.x as f64, p.y as f64);
+        // Where the edge from p to q, on opposite sides of the line, crosses it.
+        let crossing = |p: Point, q: Point| {
+            let t = side(p) as f64 / (side(p) - side(q)) as f64;
+            (p.x as f64 + t * (q.x - p.x) as f64, p.y as f64 + t * (q.y - p.y) as f64)
+        };
+        // The line meets the hull where points on it are, ordered along it.
+        let ends = |on_line: Vec<(f64, f64)>| {
+            let entry = on_line.iter().copied().min_by(|&p, &q| along(p).partial_cmp(&along(q)).unwrap())?;
+            let exit = on_line.iter().copied().max_by(|&p, &q| along(p).partial_cmp(&along(q)).unwrap())?;
+            Some((entry, exit))
+        };
+        if n < 3 {
+            let mut on_line: Vec<(f64, f64)> = v.iter().filter(|&&p| side(p) == 0).map(|&p| exact(p)).collect();
+            if n == 2 && side(v[0]).signum() * side(v[1]).signum() < 0 {
+                on_line.push(crossing(v[0], v[1]));
+            }
+            return ends(on_line);
+        }
+        let next = |i: usize| (i + 1) % n;
+        let previous = |i: usize| (i + n - 1) % n;
+
+        // The side of the line is a linear function, largest left of the line at the top vertex.
+        let normal = (a.y - b.y, b.x - a.x);
+        let (top, bottom) = (self.extreme(normal, &mut |_| ()), self.extreme((-normal.0, -normal.1), &mut |_| ()));
+        if side(v[top]) < 0 || side(v[bottom]) > 0 {
+            return None;
+        }
+        if side(v[top]) == 0 || side(v[bottom]) == 0 {
+            // The line is a supporting line, through a vertex or along an edge.
+            let touch = if side(v[top]) == 0 { top } else { bottom };
+            return ends([previous(touch), touch, next(touch)].iter().filter(|&&i| side(v[i]) == 0).map(|&i| exact(v[i])).collect());
+        }
+        // Going clockwise the hull crosses from left of the line to right between the top and
+        // the bottom, where the line leaves it, and back between the bottom and the top.
+        let at = |i: usize| if side(v[i]) == 0 { exact(v[i]) } else { crossing(v[previous(i)], v[i]) };
+        let exit = self.first_after(top, bottom, |i| side(v[i]) <= 0);
+        let entry = self.first_after(bottom, top, |i| side(v[i]) >= 0);
+        Some((at(entry), at(exit)))
+    }
+
+    /// The first index after `from`, going clockwise up to and including `to`, that `found` holds
+    /// for, when it holds for `to` and, from where it first does, for every index up to `to`.
+    fn first_after(&self, from: usize, to: usize, mut found: impl FnMut(usize) -> bool) -> usize {
+        let n = self.vertices.len();
+        let steps = (to + n - from) % n;
+        (from + 1 + partition(steps - 1, |k| !found((from + 1 + k) % n))) % n
+    }
+
+    /// Where each point is, with the points shared among the threads of the current rayon pool.
+    pub fn contains_all(&self, points: &[Point]) -> Vec<Location> {
+        points.par_iter().map(|&point| self.contains(point)).collect()
+    }
+}
+
+/// The amount of indices `0..count` starting the range that `before` holds for, when it holds
+/// for a prefix of the range. Like `partition_point` for a range computed on the fly.
+fn partition(count: usize, mut before: impl FnMut(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let middle = (low + high) / 2;
+        if before(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+/// `dir`, with the result passed to `turns` to be counted.
+fn turn(a: Point, b: Point, c: Point, turns: &mut impl FnMut(i64)) -> i64 {
+    let direction = dir(a, b, c);
+    turns(direction);
+    direction
+}
+
+/// Whether direction `a` comes before `b` going clockwise from `reference`, which itself comes first.
+pub(crate) fn clockwise_before(reference: (i128, i128), a: (i128, i128), b: (i128, i128)) -> bool {
+    // The first half turn from the reference, including it, and the second half turn.
+    let half = |v: (i128, i128)| {
+        let cross = reference.0 * v.1 - reference.1 * v.0;
+        if cross < 0 || (cross == 0 && reference.0 * v.0 + reference.1 * v.1 > 0) { 0 } else { 1 }
+    };
+    half(a) < half(b) || (half(a) == half(b) && a.0 * b.1 - a.1 * b.0 < 0)
+}
+
+fn distance_squared(a: Point, b: Point) -> i64 {
+    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+}
+
+impl From<&Hull> for HullIndex {
+    fn from(hull: &Hull) -> Self {
+        HullIndex::new(hull)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hull::Hull;
+    use crate::metrics::Metrics;
+    use crate::query::{distance_squared, HullIndex, Location};
+    use std::f64::consts::PI;
+    use crate::{dir, inc_ch, Point};
+    use rand::Rng;
+
+    /// The location by testing the point against every edge.
+    fn scan(hull: &[Point], point: Point) -> Location {
+        let n = hull.len();
+        let turns: Vec<i64> = (0..n).map(|i| dir(hull[i], hull[(i + 1) % n], point)).collect();
+        if turns.iter().any(|&turn| turn > 0) {
+            Location::Outside
+        } else if turns.contains(&0) {
+            Location::Boundary
+        } else {
+            Location::Inside
+        }
+    }
+
+    #[test]
+    fn binary_search_matches_scanning_the_edges() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(3, 5), (20, 10), (200, 50), (1_000, 1_000_000)].iter() {
+            for _ in 0..50 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let hull = Hull::new(inc_ch(&points, &mut Metrics::new()));
+                if hull.len() < 3 {
+                    continue;
+                }
+                let index = HullIndex::new(&hull);
+                // Queries around the hull, and its vertices and points on its edges.
+                let mut queries: Vec<Point> = (0..500).map(|_| Point::new(rng.gen_range(-1, range + 1), rng.gen_range(-1, range + 1))).collect();
+                queries.extend(hull.vertices());
+                queries.extend(points.iter().filter(|&&p| scan(hull.vertices(), p) == Location::Boundary));
+                for &query in &queries {
+                    assert_eq!(index.contains(query), scan(hull.vertices(), query), "{:?} in {:?}", query, hull);
+                }
+                let sequential: Vec<Location> = queries.iter().map(|&query| index.contains(query)).collect();
+                assert_eq!(index.contains_all(&queries), sequential);
+            }
+        }
+    }
+
+    /// Where the line goes in and out of the hull, from every edge it crosses and vertex on it.
+    fn scan_line(hull: &[Point], (a, b): (Point, Point)) -> Option<((f64, f64), (f64, f64))> {
+        let n = hull.len();
+        let mut on_line = Vec::new();
+        for i in 0..n {
+            let (p, q) = (hull[i], hull[(i + 1) % n]);
+            let (side_p, side_q) = (dir(a, b, p), dir(a, b, q));
+            if side_p == 0 {
+                on_line.push((p.x as f64, p.y as f64));
+            } else if side_p.signum() * side_q.signum() < 0 {
+                let t = side_p as f64 / (side_p - side_q) as f64;
+                on_line.push((p.x as f64 + t * (q.x - p.x) as f64, p.y as f64 + t * (q.y - p.y) as f64));
+            }
+        }
+        let along = |p: &(f64, f64)| (b.x - a.x) as f64 * p.0 + (b.y - a.y) as f64 * p.1;
+        on_line.sort_by(|p, q| along(p).partial_cmp(&along(q)).unwrap());
+        Some((*on_line.first()?, *on_line.last()?))
+    }
+
+    #[test]
+    fn queries_match_scanning_the_vertices() {
+        let mut rng = rand::thread_rng();
+        for &(count, range) in [(3, 5), (20, 10), (200, 50), (1_000, 1_000_000)].iter() {
+            for _ in 0..50 {
+                let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+                let hull = Hull::new(inc_ch(&points, &mut Metrics::new()));
+                let index = HullIndex::new(&hull);
+                let v = hull.vertices();
+                for _ in 0..200 {
+                    let direction = (rng.gen_range(-3, 4), rng.gen_range(-3, 4));
+                    let dot = |p: Point| direction.0 * p.x + direction.1 * p.y;
+                    let extreme = index.extreme_in_direction(direction).unwrap();
+                    assert_eq!(dot(extreme), v.iter().map(|&p| dot(p)).max().unwrap(), "{:?} in {:?}", direction, hull);
+
+                    let point = Point::new(rng.gen_range(-range, 2 * range), rng.gen_range(-range, 2 * range));
+                    match index.tangents_from(point) {
+                        None => assert!(v == [point] || (v.len() >= 3 && scan(v, point) == Location::Inside), "{:?} in {:?}", point, hull),
+                        Some((left, right)) => {
+                            // No vertex is beyond the tangent on the line to it.
+                            let farthest = |tangent: Point| v.iter()
+                                .filter(|&&p| dir(point, tangent, p) == 0 && (p.x - point.x) * (tangent.x - point.x) + (p.y - point.y) * (tangent.y - point.y) > 0)
+                                .all(|&p| distance_squared(point, p) <= distance_squared(point, tangent));
+                            assert!(v.iter().all(|&p| dir(point, left, p) >= 0) && farthest(left), "left {:?} of {:?} in {:?}", left, point, hull);
+                            assert!(v.iter().all(|&p| dir(point, right, p) <= 0) && farthest(right), "right {:?} of {:?} in {:?}", right, point, hull);
+                        }
+                    }
+
+                    let other = Point::new(rng.gen_range(-range, 2 * range), rng.gen_range(-range, 2 * range));
+                    if other == point {
+                        continue;
+                    }
+                    let close = |p: (f64, f64), q: (f64, f64)| (p.0 - q.0).abs() + (p.1 - q.1).abs() < 1e-6 * range as f64;
+                    match (index.line_intersection((point, other)), scan_line(v, (point, other))) {
+                        (Some((entry, exit)), Some((expected_entry, expected_exit))) => {
+                            assert!(close(entry, expected_entry) && close(exit, expected_exit), "{:?} to {:?} through {:?}", point, other, hull);
+                        }
+                        (found, expected) => assert_eq!(found, expected, "{:?} to {:?} through {:?}", point, other, hull),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tangents_and_lines_of_a_square() {
+        let index = HullIndex::new(&Hull::new(vec![Point::new(0, 0), Point::new(0, 4), Point::new(4, 4), Point::new(4, 0)]));
+        assert_eq!(index.extreme_in_direction((1, 1)), Some(Point::new(4, 4)));
+        // Of the two vertices of the top edge, the one it starts at going clockwise.
+        assert_eq!(index.extreme_in_direction((0, 1)), Some(Point::new(0, 4)));
+        assert_eq!(index.tangents_from(Point::new(2, 10)), Some((Point::new(0, 4), Point::new(4, 4))));
+        // On the line through the left edge, so the farther end of it is the left tangent.
+        assert_eq!(index.tangents_from(Point::new(0, 6)), Some((Point::new(0, 0), Point::new(4, 4))));
+        assert_eq!(index.tangents_from(Point::new(4, 4)), Some((Point::new(0, 4), Point::new(4, 0))));
+        assert_eq!(index.tangents_from(Point::new(2, 0)), Some((Point::new(4, 0), Point::new(0, 0))));
+        assert_eq!(index.tangents_from(Point::new(2, 2)), None);
+        assert_eq!(index.line_intersection((Point::new(5, 2), Point::new(-1, 2))), Some(((4.0, 2.0), (0.0, 2.0))));
+        assert_eq!(index.line_intersection((Point::new(-1, -1), Point::new(1, 1))), Some(((0.0, 0.0), (4.0, 4.0))));
+        assert_eq!(index.line_intersection((Point::new(-2, 4), Point::new(-1, 4))), Some(((0.0, 4.0), (4.0, 4.0))));
+        assert_eq!(index.line_intersection((Point::new(8, 0), Point::new(0, 8))), Some(((4.0, 4.0), (4.0, 4.0))));
+        assert_eq!(index.line_intersection((Point::new(9, 0), Point::new(0, 9))), None);
+    }
+
+    #[test]
+    fn tangents_and_extremes_count_logarithmic_turns() {
+        // Every point is a vertex of the hull of points on a circle.
+        let points: Vec<Point> = (0..4096)
+            .map(|i| i as f64 * 2.0 * PI / 4096.0)
+            .map(|a| Point::new((1e9 * a.cos()) as i64, (1e9 * a.sin()) as i64))
+            .collect();
+        let hull = Hull::new(inc_ch(&points, &mut Metrics::new()));
+        let index = HullIndex::new(&hull);
+        let mut turns = 0;
+        index.extreme_in_direction_counted((3, 7), &mut |_| turns += 1);
+        assert!(turns > 0 && turns <= 2 * 13, "{} turns", turns);
+        let mut turns = 0;
+        index.tangents_from_counted(Point::new(3_000_000_000, 1), &mut |_| turns += 1).unwrap();
+        assert!(turns > 0 && turns <= 8 * 13, "{} turns for {} vertices", turns, hull.len());
+    }
+
+    #[test]
+    fn degenerate_hulls() {
+        let empty = HullIndex::new(&Hull::new(Vec::new()));
+        assert_eq!(empty.contains(Point::new(0, 0)), Location::Outside);
+        let point = HullIndex::new(&Hull::new(vec![Point::new(2, 3)]));
+        assert_eq!(point.contains(Point::new(2, 3)), Location::Boundary);
+        assert_eq!(point.contains(Point::new(3, 2)), Location::Outside);
+        let segment = HullIndex::new(&Hull::new(vec![Point::new(4, 2), Point::new(0, 0)]));
+        assert_eq!(segment.contains(Point::new(2, 1)), Location::Boundary);
+        assert_eq!(segment.contains(Point::new(0, 0)), Location::Boundary);
+        assert_eq!(segment.contains(Point::new(6, 3)), Location::Outside);
+        assert_eq!(segment.contains(Point::new(2, 2)), Location::Outside);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+use crate::Method;
+use convexhull::hull::Hull;
+use convexhull::metrics::Metrics;
+use crate::stats::Summary;
+
+/// How results are printed: aligned for reading, or one comma separated row per value for scripts.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    Human,
+    Csv,
+}
+
+/// The area and perimeter of the hull a run returned.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Shape {
+    pub twice_area: i128,
+    pub perimeter: f64,
+}
+
+impl Shape {
+    pub fn of(hull: &Hull) -> Self {
+        Shape { twice_area: hull.twice_area(), perimeter: hull.perimeter() }
+    }
+}
+
+impl fmt::Display for Shape {
+    /// The area is printed exactly, which is a whole number or a half.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let half = if self.twice_area % 2 == 0 { "" } else { ".5" };
+        write!(f, "Area: {}{}, Perimeter: {:.3}", self.twice_area / 2, half, self.perimeter)
+    }
+}
+
+fn print_lengths(input_size: usize, output_size: usize, shape: Option<Shape>) {
+    match shape {
+        Some(shape) => println!("Input length: {}, Output length: {}, {}", input_size, output_size, shape),
+        None => println!("Input length: {}, Output length: {}", input_size, output_size),
+    }
+}
+
+/// The metrics of a single run of an algorithm, and the shape of its hull for algorithms that
+/// return one.
+pub struct Sample {
+    pub output_size: usize,
+    pub shape: Option<Shape>,
+    pub metrics: Metrics,
+}
+
+impl Sample {
+    pub fn new(output_size: usize, shape: Option<Shape>, metrics: Metrics) -> Self {
+        Sample { output_size, shape, metrics }
+    }
+
+    pub fn print(&self, method: Method, input_size: usize, format: Format) {
+        let time = self.metrics.time_elapsed().as_nanos();
+        let counters = self.metrics.counters();
+        match format {
+            Format::Csv => {
+                let mut values: Vec<String> = counters.iter().map(|(_, value)| value.to_string()).collect();
+                values.extend(self.metrics.measures().iter().map(|(_, value)| value.to_string()));
+                println!("{:?},{},{},{},{}", method, input_size, self.output_size, time, values.join(","));
+            }
+            Format::Human => {
+                println!("------- Method: {:?} statistics -------", method);
+                print_lengths(input_size, self.output_size, self.shape);
+                println!("Ran in {} nanoseconds", time);
+                for (name, elapsed) in self.metrics.phases() {
+                    println!("{}: {} nanoseconds", name, elapsed.as_nanos());
+                }
+                for (name, value) in counters {
+                    println!("{}: {}", name, value);
+                }
+                for (name, value) in self.metrics.measures() {
+                    println!("{}: {:.3}", name, value);
+                }
+                println!("-------------------------------------");
+                println!();
+            }
+        }
+    }
+}
+
+/// Statistics over the counted samples of one algorithm.
+pub struct Report {
+    pub method: Method,
+    pub input_size: usize,
+    pub output_size: usize,
+    pub shape: Option<Shape>,
+    pub time: Summary,
+    pub counters: Vec<(&'static str, Summary)>,
+    pub measures: Vec<(&'static str, Summary)>,
+    pub phases: Vec<(&'static str, Summary)>,
+}
+
+impl Report {
+    pub fn new(method: Method, input_size: usize, samples: &[Sample]) -> Self {
+        let times: Vec<f64> = samples.iter().map(|s| s.metrics.time_elapsed().as_nanos() as f64).collect();
+        let counters = samples[0].metrics.counters()
+            .iter()
+            .map(|(name, _)| {
+                let values: Vec<f64> = samples.iter().map(|s| s.metrics.counter(name) as f64).collect();
+                (*name, Summary::new(&values))
+            })
+            .collect();
+        let measures = samples[0].metrics.measures()
+            .iter()
+            .map(|(name, _)| {
+                let values: Vec<f64> = samples
+                    .iter()
+                    .map(|s| s.metrics.measures().iter().find(|(n, _)| n == name).map_or(0.0, |(_, value)| *value))
+                    .collect();
+                (*name, Summary::new(&values))
+            })
+            .collect();
+        let phases = samples[0].metrics.phases()
+            .iter()
+            .map(|(name, _)| {
+                let values: Vec<f64> = samples
+                    .iter()
+                    .map(|s| s.metrics.phases().iter().find(|(n, _)| n == name).map_or(0.0, |(_, t)| t.as_nanos() as f64))
+                    .collect();
+                (*name, Summary::new(&values))
+            })
+            .collect();
+        Report {
+            method,
+            input_size,
+            output_size: samples[0].output_size,
+            shape: samples[0].shape,
+            time: Summary::new(&times),
+            counters,
+            measures,
+            phases,
+        }
+    }
+
+    pub fn counter(&self, name: &str) -> Option<&Summary> {
+        self.counters.iter().find(|(n, _)| *n == name).map(|(_, summary)| summary)
+    }
+
+    /// Every timer, counter and measure, named as in CSV output and baselines.
+    pub fn metrics(&self) -> Vec<(String, &Summary)> {
+        let mut metrics = vec![(String::from("time_ns"), &self.time)];
+        metrics.extend(self.phases.iter().map(|(name, summary)| (format!("time_ns[{}]", name), summary)));
+        metrics.extend(self.counters.iter().map(|(name, summary)| (name.to_string(), summary)));
+        metrics.extend(self.measures.iter().map(|(name, summary)| (name.to_string(), summary)));
+        metrics
+    }
+
+    pub fn print(&self, format: Format) {
+        match format {
+            Format::Csv => {
+                for (name, s) in self.metrics() {
+                    println!("{:?},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                             self.method,
+                             self.input_size, self.output_size,
+                             name, s.samples,
+                             s.min, s.max, s.mean, s.median, s.std_dev,
+                             s.ci95.0, s.ci95.1,
+                             s.outliers.low_severe, s.outliers.low_mild, s.outliers.high_mild, s.outliers.high_severe
+                    );
+                }
+            }
+            Format::Human => {
+                println!("------- Method: {:?} summary -------", self.method);
+                print_lengths(self.input_size, self.output_size, self.shape);
+                println!("{} samples", self.time.samples);
+                println!("{:<24} {:>14} {:>14} {:>14} {:>14} {:>14} {:>31} {:>18}",
+                         "metric", "min", "max", "mean", "median", "std dev", "95% CI of mean", "outliers");
+                for (name, s) in self.metrics() {
+                    // Times and counts are whole, but measures like distances are fractions.
+                    let is_measure = self.measures.iter().any(|(n, _)| *n == name);
+                    let (whole, fraction) = if is_measure { (3, 3) } else { (0, 1) };
+                    println!("{:<24} {:>14.*} {:>14.*} {:>14.*} {:>14.*} {:>14.*} {:>31} {:>18}",
+                             name, whole, s.min, whole, s.max, fraction, s.mean, fraction, s.median, fraction, s.std_dev,
+                             format!("[{:.*}, {:.*}]", fraction, s.ci95.0, fraction, s.ci95.1),
+                             format!("{} ({}/{}/{}/{})", s.outliers.total(), s.outliers.low_severe, s.outliers.low_mild, s.outliers.high_mild, s.outliers.high_severe));
+                }
+                println!("Outliers are split into (low severe/low mild/high mild/high severe)");
+                if !self.phases.is_empty() {
+                    let shares: Vec<String> = self.phases
+                        .iter()
+                        .map(|(name, s)| format!("{} {:.1}%", name, s.mean / self.time.mean * 100.0))
+                        .collect();
+                    println!("Share of mean time: {}", shares.join(", "));
+                }
+                println!("-------------------------------------");
+                println!();
+            }
+        }
+    }
+}
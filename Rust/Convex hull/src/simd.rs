@@ -0,0 +1,261 @@
+//! Gift wrapping over a structure-of-arrays point layout.
+//! With the `simd` feature, which needs a nightly compiler, a wrapping step tests blocks of
+//! points against its candidate with portable SIMD. Without it the same kernel is a plain loop
+//! over the coordinate slices. Both count the same turns.
+//!
+//! Unlike `gift_ch`, a wrapping step takes the farthest of collinear candidates, so the hull
+//! doesn't depend on the order points are compared in, and it matches `inc_ch`.
+
+use std::time::Instant;
+use crate::metrics::Metrics;
+use crate::{dir, Point};
+
+/// Points stored as separate x and y coordinate arrays.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Points {
+    pub xs: Vec<i64>,
+    pub ys: Vec<i64>,
+    #[cfg(feature = "simd")]
+    float_xs: Vec<f64>,
+    #[cfg(feature = "simd")]
+    float_ys: Vec<f64>,
+    #[cfg(feature = "simd")]
+    exact_in_f64: bool,
+}
+
+impl Points {
+    pub fn new(points: &[Point]) -> Self {
+        Points {
+            xs: points.iter().map(|p| p.x).collect(),
+            ys: points.iter().map(|p| p.y).collect(),
+            #[cfg(feature = "simd")]
+            float_xs: points.iter().map(|p| p.x as f64).collect(),
+            #[cfg(feature = "simd")]
+            float_ys: points.iter().map(|p| p.y as f64).collect(),
+            #[cfg(feature = "simd")]
+            exact_in_f64: spans_less_than(points, 1 << 26),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.xs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xs.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Point {
+        Point::new(self.xs[i], self.ys[i])
+    }
+
+    /// The index of the smallest point, by x and then y.
+    pub fn lowest(&self) -> Option<usize> {
+        (0..self.len()).min_by_key(|&i| (self.xs[i], self.ys[i]))
+    }
+}
+
+/// Whether the x and y coordinates each span less than `span`.
+#[cfg(feature = "simd")]
+fn spans_less_than(points: &[Point], span: i64) -> bool {
+    let spans = |coordinates: &mut dyn Iterator<Item = i64>| {
+        let (min, max) = coordinates.fold((i64::MAX, i64::MIN), |(min, max), c| (min.min(c), max.max(c)));
+        max.saturating_sub(min) < span
+    };
+    spans(&mut points.iter().map(|p| p.x)) && spans(&mut points.iter().map(|p| p.y))
+}
+
+fn distance_squared(a: Point, b: Point) -> i64 {
+    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+}
+
+/// The state of a wrapping step: the best candidate for the hull point after `from` so far,
+/// and the turns counted while finding it.
+struct Wrap {
+    from: Point,
+    best: usize,
+    candidate: Point,
+    right_turns: i64,
+    left_turns: i64,
+    no_turns: i64,
+}
+
+impl Wrap {
+    /// Starts from the first point that isn't `from`.
+    fn new(points: &Points, from: Point) -> Option<Self> {
+        let best = (0..points.len()).find(|&i| points.get(i) != from)?;
+        Some(Wrap { from, best, candidate: points.get(best), right_turns: 0, left_turns: 0, no_turns: 0 })
+    }
+
+    /// Replaces the candidate by `point` if it is to the right of the line from `from` to the
+    /// candidate, or collinear and farther away.
+    fn offer(&mut self, i: usize, point: Point) {
+        if point == self.from {
+            return;
+        }
+        let direction = dir(self.from, self.candidate, point);
+        if direction > 0 {
+            self.right_turns += 1;
+        } else if direction < 0 {
+            self.left_turns += 1;
+            return;
+        } else {
+            self.no_turns += 1;
+            if distance_squared(self.from, point) <= distance_squared(self.from, self.candidate) {
+                return;
+            }
+        }
+        self.best = i;
+        self.candidate = point;
+    }
+
+    fn finish(self, test_struct: &mut Metrics) -> usize {
+        test_struct.count("right_turns", self.right_turns);
+        test_struct.count("left_turns", self.left_turns);
+        test_struct.count("no_turns", self.no_turns);
+        self.best
+    }
+}
+
+/// The index of the hull point after `from`: the point with no other point to the right of the
+/// line from `from` to it, and the farthest one if several are collinear.
+/// Returns `None` when every point equals `from`.
+#[cfg(not(feature = "simd"))]
+pub fn wrap_step(points: &Points, from: Point, test_struct: &mut Metrics) -> Option<usize> {
+    let mut wrap = Wrap::new(points, from)?;
+    for i in wrap.best + 1..points.len() {
+        wrap.offer(i, points.get(i));
+    }
+    Some(wrap.finish(test_struct))
+}
+
+/// The index of the hull point after `from`: the point with no other point to the right of the
+/// line from `from` to it, and the farthest one if several are collinear.
+/// Returns `None` when every point equals `from`.
+///
+/// Blocks of `LANES` points are tested against the current candidate at once. The candidate
+/// rarely changes, so most blocks only hold left turns and are counted and skipped; the others
+/// are offered one point at a time. Coordinates are compared as `f64`, which is exact while
+/// they span less than 2^26, and larger inputs fall back to the scalar loop.
+#[cfg(feature = "simd")]
+pub fn wrap_step(points: &Points, from: Point, test_struct: &mut Metrics) -> Option<usize> {
+    use std::simd::prelude::*;
+    const LANES: usize = 8;
+    type Lanes = Simd<f64, LANES>;
+
+    let mut wrap = Wrap::new(points, from)?;
+    let blocks_start = usize::min(points.len(), (wrap.best + LANES) / LANES * LANES);
+    for i in wrap.best + 1..blocks_start {
+        wrap.offer(i, points.get(i));
+    }
+    let blocks_end = if points.exact_in_f64 { blocks_start + (points.len() - blocks_start) / LANES * LANES } else { blocks_start };
+
+    let (from_x, from_y) = (Lanes::splat(from.x as f64), Lanes::splat(from.y as f64));
+    let zero = Lanes::splat(0.0);
+    for start in (blocks_start..blocks_end).step_by(LANES) {
+        let x = Lanes::from_slice(&points.float_xs[start..start + LANES]);
+        let y = Lanes::from_slice(&points.float_ys[start..start + LANES]);
+        let candidate_x = Lanes::splat(wrap.candidate.x as f64);
+        let candidate_y = Lanes::splat(wrap.candidate.y as f64);
+        let direction = (candidate_x - from_x) * (y - from_y) - (candidate_y - from_y) * (x - from_x);
+        if direction.simd_ge(zero).any() {
+            for i in start..start + LANES {
+                wrap.offer(i, points.get(i));
+            }
+        } else {
+            // Every point in the block is a left turn, since copies of `from` would be on the line.
+            wrap.left_turns += LANES as i64;
+        }
+    }
+    for i in blocks_end..points.len() {
+        wrap.offer(i, points.get(i));
+    }
+    Some(wrap.finish(test_struct))
+}
+
+/// Gift wrapping from the lowest point, one `wrap_step` per hull point.
+pub(crate) fn gift_wrap_soa(points: &Points, test_struct: &mut Metrics) -> Vec<Point> {
+    let first = match points.lowest() {
+        Some(i) => points.get(i),
+        None => return Vec::new(),
+    };
+    let mut hull = vec![first];
+    while let Some(i) = wrap_step(points, hull[hull.len() - 1], test_struct) {
+        let next = points.get(i);
+        if next == first {
+            break;
+        }
+        hull.push(next);
+    }
+    hull
+}
+
+/// Gift wrapping with vectorised wrapping steps, in the same order as `inc_ch` returns the hull.
+pub fn gift_ch_simd(points: &[Point], test_struct: &mut Metrics) -> Vec<Point> {
+    test_struct.set_start_time();
+    let phase = Instant::now();
+    let points = Points::new(points);
+    test_struct.end_phase("layout", phase);
+    let phase = Instant::now();
+    let hull = gift_wrap_soa(&points, test_struct);
+    test_struct.end_phase("wrap", phase);
+    test_struct.set_end_time();
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::Metrics;
+    use crate::simd::{gift_ch_simd, wrap_step, Points};
+    use crate::{ch_ch_simd, inc_ch, Point};
+    use rand::Rng;
+
+    #[test]
+    fn simd_hulls_match_inc_ch() {
+        let mut rng = rand::thread_rng();
+        for round in 0..200 {
+            let (count, range) = if round % 10 == 0 { (5000, 1000) } else { (rng.gen_range(1, 80), 20) };
+            let points: Vec<Point> = (0..count).map(|_| Point::new(rng.gen_range(0, range), rng.gen_range(0, range))).collect();
+            let mut expected = inc_ch(&points, &mut Metrics::new());
+            // inc_ch returns hulls of one or two points in hash order, and repeats equal points.
+            if expected.len() <= 2 {
+                expected.sort();
+                expected.dedup();
+            }
+            assert_eq!(gift_ch_simd(&points, &mut Metrics::new()), expected, "gift_ch_simd of {:?}", points);
+            assert_eq!(ch_ch_simd(&points, &mut Metrics::new()), expected, "ch_ch_simd of {:?}", points);
+        }
+    }
+
+    #[test]
+    fn simd_hulls_of_degenerate_inputs() {
+        let cases = [
+            (vec![], vec![]),
+            (vec![Point::new(1, 1)], vec![Point::new(1, 1)]),
+            (vec![Point::new(1, 1); 9], vec![Point::new(1, 1)]),
+            (vec![Point::new(3, 1), Point::new(0, 0)], vec![Point::new(0, 0), Point::new(3, 1)]),
+            (vec![Point::new(3, 1), Point::new(0, 0), Point::new(3, 1), Point::new(0, 0)], vec![Point::new(0, 0), Point::new(3, 1)]),
+            ((0..20).map(|i| Point::new(i, 2 * i)).rev().collect(), vec![Point::new(0, 0), Point::new(19, 38)]),
+            ((0..20).map(|i| Point::new(2, i % 7)).collect(), vec![Point::new(2, 0), Point::new(2, 6)]),
+            ((0..20).map(|i| Point::new(i % 5, 5)).collect(), vec![Point::new(0, 5), Point::new(4, 5)]),
+        ];
+        for (points, expected) in cases.iter() {
+            assert_eq!(gift_ch_simd(points, &mut Metrics::new()), *expected, "gift_ch_simd of {:?}", points);
+            assert_eq!(ch_ch_simd(points, &mut Metrics::new()), *expected, "ch_ch_simd of {:?}", points);
+        }
+    }
+
+    #[test]
+    fn wrap_step_counts_every_comparison() {
+        let points = Points::new(&[
+            Point::new(0, 0), Point::new(4, 0), Point::new(2, 2), Point::new(0, 4),
+            Point::new(4, 4), Point::new(0, 0), Point::new(2, 0), Point::new(1, 3),
+            Point::new(3, 1),
+        ]);
+        let mut metrics = Metrics::new();
+        assert_eq!(wrap_step(&points, Point::new(0, 0), &mut metrics), Some(3));
+        // Every point except the first candidate and the two copies of the start is compared.
+        assert_eq!(metrics.total_turns(), 6);
+        assert_eq!(wrap_step(&Points::new(&[Point::new(1, 1); 5]), Point::new(1, 1), &mut metrics), None);
+    }
+}
@@ -0,0 +1,236 @@
+/// Two-sided 95% critical values of Student's t distribution for 1 to 30 degrees of freedom.
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+/// Critical value of the two-sided 95% t distribution.
+/// Above 30 degrees of freedom the Cornish-Fisher expansion around the normal quantile is
+/// accurate to the third decimal.
+pub fn t_critical_95(degrees_of_freedom: usize) -> f64 {
+    if degrees_of_freedom == 0 {
+        return f64::INFINITY;
+    }
+    if degrees_of_freedom <= T_95.len() {
+        return T_95[degrees_of_freedom - 1];
+    }
+    let z: f64 = 1.959_964;
+    let df = degrees_of_freedom as f64;
+    z + (z.powi(3) + z) / (4.0 * df) + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df * df)
+}
+
+/// Probability that the absolute value of a Student's t distributed variable with
+/// `degrees_of_freedom`, which need not be whole, is at least `|t|`: the two-sided p-value of `t`.
+/// This is the regularized incomplete beta function I_x(df / 2, 1 / 2) at x = df / (df + t^2).
+pub fn t_p_value(t: f64, degrees_of_freedom: f64) -> f64 {
+    let x = degrees_of_freedom / (degrees_of_freedom + t * t);
+    incomplete_beta(x, degrees_of_freedom / 2.0, 0.5)
+}
+
+/// The regularized incomplete beta function I_x(a, b), by its continued fraction evaluated with
+/// Lentz's method. The fraction converges fast for x below (a + 1) / (a + b + 2), and the symmetry
+/// I_x(a, b) = 1 - I_(1 - x)(b, a) covers the other side.
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - incomplete_beta(1.0 - x, b, a);
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+
+    const TINY: f64 = 1e-300;
+    let (mut c, mut d) = (1.0, 1.0 - (a + b) * x / (a + 1.0));
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut fraction = d;
+    for m in 1..300 {
+        let m = m as f64;
+        // Every step of the fraction takes an even and an odd term.
+        for &numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ].iter() {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            fraction *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    front * fraction
+}
+
+/// The logarithm of the gamma function of a positive `x`, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46, -86.505_320_329_416_77, 24.014_098_240_830_91,
+        -1.231_739_572_450_155, 0.001_208_650_973_866_179, -0.000_005_395_239_384_953,
+    ];
+    let t = x + 5.5;
+    let mut series = 1.000_000_000_190_015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -t + (x + 0.5) * t.ln() + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Samples outside the Tukey fences.
+/// Mild outliers lie between 1.5 and 3 interquartile ranges outside the quartiles, severe ones further out.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Outliers {
+    pub low_severe: usize,
+    pub low_mild: usize,
+    pub high_mild: usize,
+    pub high_severe: usize,
+}
+
+impl Outliers {
+    pub fn total(&self) -> usize {
+        self.low_severe + self.low_mild + self.high_mild + self.high_severe
+    }
+}
+
+/// Descriptive statistics of a set of samples.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Summary {
+    pub samples: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    /// 95% confidence interval of the mean.
+    pub ci95: (f64, f64),
+    pub outliers: Outliers,
+}
+
+impl Summary {
+    pub fn new(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "Cannot summarise zero samples");
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let n = sorted.len();
+
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let std_dev = if n > 1 {
+            (sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let half_width = if n > 1 {
+            t_critical_95(n - 1) * std_dev / (n as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        let q1 = percentile(&sorted, 0.25);
+        let q3 = percentile(&sorted, 0.75);
+        let iqr = q3 - q1;
+        let mut outliers = Outliers::default();
+        for &s in &sorted {
+            if s < q1 - 3.0 * iqr {
+                outliers.low_severe += 1;
+            } else if s < q1 - 1.5 * iqr {
+                outliers.low_mild += 1;
+            } else if s > q3 + 3.0 * iqr {
+                outliers.high_severe += 1;
+            } else if s > q3 + 1.5 * iqr {
+                outliers.high_mild += 1;
+            }
+        }
+
+        Summary {
+            samples: n,
+            min: sorted[0],
+            max: sorted[n - 1],
+            mean,
+            median: percentile(&sorted, 0.5),
+            std_dev,
+            ci95: (mean - half_width, mean + half_width),
+            outliers,
+        }
+    }
+}
+
+/// Linearly interpolated percentile of an ascending slice, `p` in [0, 1].
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::{Summary, t_critical_95, t_p_value, Outliers, T_95};
+
+    #[test]
+    fn summary_of_known_samples() {
+        let summary = Summary::new(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(summary.samples, 8);
+        assert_eq!(summary.min, 2.0);
+        assert_eq!(summary.max, 9.0);
+        assert_eq!(summary.mean, 5.0);
+        assert_eq!(summary.median, 4.5);
+        assert!((summary.std_dev - 2.138_090).abs() < 1e-6);
+        let half_width = 2.365 * summary.std_dev / 8f64.sqrt();
+        assert!((summary.ci95.0 - (5.0 - half_width)).abs() < 1e-9);
+        assert!((summary.ci95.1 - (5.0 + half_width)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn summary_of_single_sample() {
+        let summary = Summary::new(&[42.0]);
+        assert_eq!(summary.median, 42.0);
+        assert_eq!(summary.std_dev, 0.0);
+        assert_eq!(summary.ci95, (42.0, 42.0));
+        assert_eq!(summary.outliers.total(), 0);
+    }
+
+    #[test]
+    fn outliers_are_classified_by_tukey_fences() {
+        let mut samples: Vec<f64> = (0..20).map(|i| 100.0 + (i % 5) as f64).collect();
+        samples.push(110.0);
+        samples.push(1000.0);
+        samples.push(-1000.0);
+        let summary = Summary::new(&samples);
+        assert_eq!(summary.outliers, Outliers { low_severe: 1, low_mild: 0, high_mild: 1, high_severe: 1 });
+    }
+
+    #[test]
+    fn t_critical_values() {
+        assert_eq!(t_critical_95(1), 12.706);
+        assert_eq!(t_critical_95(30), 2.042);
+        assert!((t_critical_95(60) - 2.000).abs() < 1e-3);
+        assert!((t_critical_95(120) - 1.980).abs() < 1e-3);
+        assert!((t_critical_95(1_000_000) - 1.960).abs() < 1e-3);
+    }
+
+    #[test]
+    fn p_values_of_critical_values_are_five_percent() {
+        for (i, &t) in T_95.iter().enumerate() {
+            let p = t_p_value(t, (i + 1) as f64);
+            assert!((p - 0.05).abs() < 5e-4, "p = {} at {} degrees of freedom", p, i + 1);
+        }
+        assert_eq!(t_p_value(0.0, 5.0), 1.0);
+        assert!(t_p_value(1_000.0, 5.0) < 1e-10);
+        // Far out, the t distribution is the normal one.
+        assert!((t_p_value(1.959_964, 1e9) - 0.05).abs() < 1e-6);
+        assert!((t_p_value(2.575_829, 1e9) - 0.01).abs() < 1e-6);
+    }
+}
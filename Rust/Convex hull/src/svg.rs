@@ -0,0 +1,83 @@
+//! Plots of points and polygons as SVG, with y pointing up like in the hull algorithms.
+
+use std::io::{self, Write};
+use crate::circle::Circle;
+use crate::Point;
+
+/// Writes an SVG with the points as grey dots and every polygon outlined in its own colour.
+/// The colours go around the hue circle in the order of the polygons, so nested polygons like
+/// convex layers fade from red on the outside through the spectrum to the inside.
+/// Circles are drawn dashed in black, and the drawing is large enough to hold them.
+pub fn write_svg<W: Write>(mut writer: W, points: &[Point], polygons: &[Vec<Point>], circles: &[Circle]) -> io::Result<()> {
+    // The corners of the squares around the circles, rounded outwards.
+    let circle_bounds: Vec<Point> = circles
+        .iter()
+        .flat_map(|circle| {
+            let (x, y) = (circle.centre().0.to_f64(), circle.centre().1.to_f64());
+            let radius = circle.radius();
+            vec![Point::new((x - radius).floor() as i64, (y - radius).floor() as i64),
+                 Point::new((x + radius).ceil() as i64, (y + radius).ceil() as i64)]
+        })
+        .collect();
+    let all = || points.iter().chain(polygons.iter().flatten()).chain(circle_bounds.iter());
+    let (min_x, max_x) = (all().map(|p| p.x).min().unwrap_or(0), all().map(|p| p.x).max().unwrap_or(0));
+    let (min_y, max_y) = (all().map(|p| p.y).min().unwrap_or(0), all().map(|p| p.y).max().unwrap_or(0));
+    let span = i64::max(1, i64::max(max_x - min_x, max_y - min_y));
+    let margin = span / 50 + 1;
+    let radius = span as f64 / 400.0;
+
+    writeln!(writer, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="800" height="800">"#,
+             min_x - margin, -max_y - margin, max_x - min_x + 2 * margin, max_y - min_y + 2 * margin)?;
+    // Flipping y puts the origin at the bottom left.
+    writeln!(writer, r#"<g transform="scale(1,-1)">"#)?;
+    for p in points {
+        writeln!(writer, r##"<circle cx="{}" cy="{}" r="{}" fill="#888"/>"##, p.x, p.y, radius)?;
+    }
+    for (i, polygon) in polygons.iter().enumerate() {
+        let hue = 360 * i / polygons.len();
+        let vertices: Vec<String> = polygon.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+        writeln!(writer, r#"<polygon points="{}" fill="none" stroke="hsl({},80%,45%)" stroke-width="{}" stroke-linejoin="round"/>"#,
+                 vertices.join(" "), hue, radius)?;
+    }
+    for circle in circles {
+        let (x, y) = circle.centre();
+        writeln!(writer, r##"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="#000" stroke-width="{}" stroke-dasharray="{}"/>"##,
+                 x.to_f64(), y.to_f64(), circle.radius(), radius, 4.0 * radius)?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circle::welzl;
+    use crate::metrics::Metrics;
+    use crate::svg::write_svg;
+    use crate::Point;
+
+    #[test]
+    fn polygons_get_distinct_colours() {
+        let square = vec![Point::new(0, 0), Point::new(0, 4), Point::new(4, 4), Point::new(4, 0)];
+        let inner = vec![Point::new(1, 1), Point::new(2, 3), Point::new(3, 1)];
+        let mut out = Vec::new();
+        write_svg(&mut out, &[Point::new(2, 2)], &[square, inner], &[]).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert!(svg.contains(r#"points="0,0 0,4 4,4 4,0" fill="none" stroke="hsl(0,80%,45%)""#));
+        assert!(svg.contains(r#"points="1,1 2,3 3,1" fill="none" stroke="hsl(180,80%,45%)""#));
+    }
+
+    #[test]
+    fn circles_fit_in_the_drawing() {
+        let points = [Point::new(0, 0), Point::new(10, 0)];
+        let circle = welzl(&points, &mut rand::thread_rng(), &mut Metrics::new()).unwrap();
+        let mut out = Vec::new();
+        write_svg(&mut out, &points, &[], &[circle]).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.contains(r#"<circle cx="5" cy="0" r="5" fill="none""#));
+        // The circle reaches 5 below and above the points, and the margin is 1.
+        assert!(svg.contains(r#"viewBox="-1 -6 12 12""#), "{}", svg);
+    }
+}
@@ -0,0 +1,148 @@
+/// Parses a size range written as `from..to`.
+pub fn parse_range(range: &str) -> Result<(i64, i64), String> {
+    let bounds: Vec<&str> = range.split("..").collect();
+    if bounds.len() != 2 {
+        return Err(format!("{} is not a range of the form from..to", range));
+    }
+    let from = bounds[0].trim().parse::<i64>().map_err(|e| format!("{}: {}", bounds[0], e))?;
+    let to = bounds[1].trim().parse::<i64>().map_err(|e| format!("{}: {}", bounds[1], e))?;
+    if from < 1 || to < from {
+        return Err(format!("{} must satisfy 1 <= from <= to", range));
+    }
+    Ok((from, to))
+}
+
+/// `steps` problem sizes spaced evenly on a logarithmic scale between `from` and `to`, inclusive.
+/// Sizes that round to the same integer are only returned once.
+pub fn log_spaced_sizes(from: i64, to: i64, steps: usize) -> Vec<i64> {
+    if steps <= 1 || from == to {
+        return vec![from];
+    }
+    let (log_from, log_to) = ((from as f64).ln(), (to as f64).ln());
+    let mut sizes: Vec<i64> = (0..steps)
+        .map(|i| (log_from + (log_to - log_from) * i as f64 / (steps - 1) as f64).exp().round() as i64)
+        .collect();
+    sizes.dedup();
+    sizes
+}
+
+/// A cost measured for input size `n` with hull size `h`.
+#[derive(Debug, Copy, Clone)]
+pub struct Observation {
+    pub n: usize,
+    pub h: usize,
+    pub cost: f64,
+}
+
+/// The complexity classes a sweep is fitted against.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Model {
+    N,
+    NLogN,
+    NH,
+    NLogH,
+}
+
+impl Model {
+    pub const ALL: [Model; 4] = [Model::N, Model::NLogN, Model::NH, Model::NLogH];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Model::N => "n",
+            Model::NLogN => "n log n",
+            Model::NH => "nh",
+            Model::NLogH => "n log h",
+        }
+    }
+
+    fn eval(self, n: usize, h: usize) -> f64 {
+        let n = n as f64;
+        // log h is clamped at h = 2 so that degenerate hulls don't zero the model out.
+        let h = usize::max(h, 2) as f64;
+        match self {
+            Model::N => n,
+            Model::NLogN => n * n.max(2.0).log2(),
+            Model::NH => n * h,
+            Model::NLogH => n * h.log2(),
+        }
+    }
+}
+
+/// The constant `c` of `cost = c * model(n, h)` and how well it explains the observations.
+#[derive(Debug, Copy, Clone)]
+pub struct Fit {
+    pub model: Model,
+    pub constant: f64,
+    /// Root mean square of the relative residuals `(cost - c * model) / cost`.
+    pub relative_error: f64,
+}
+
+/// Fits every model to the observations, best fit first.
+/// The constant minimises the relative rather than the absolute residuals, so the largest sizes
+/// of a logarithmic sweep don't drown out the smaller ones. Observations without a cost say
+/// nothing about the relative error, so they are left out, and if none has a cost there is no fit.
+pub fn fit(observations: &[Observation]) -> Vec<Fit> {
+    let observations: Vec<_> = observations.iter().filter(|o| o.cost > 0.0).collect();
+    if observations.is_empty() {
+        return Vec::new();
+    }
+    let mut fits: Vec<Fit> = Model::ALL
+        .iter()
+        .map(|&model| {
+            let ratios: Vec<f64> = observations.iter().map(|o| model.eval(o.n, o.h) / o.cost).collect();
+            let constant = ratios.iter().sum::<f64>() / ratios.iter().map(|r| r * r).sum::<f64>();
+            let relative_error = (ratios.iter().map(|r| (1.0 - constant * r).powi(2)).sum::<f64>()
+                / ratios.len() as f64).sqrt();
+            Fit { model, constant, relative_error }
+        })
+        .collect();
+    fits.sort_by(|a, b| a.relative_error.total_cmp(&b.relative_error));
+    fits
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sweep::{fit, log_spaced_sizes, parse_range, Model, Observation};
+
+    #[test]
+    fn sizes_are_log_spaced() {
+        assert_eq!(log_spaced_sizes(1000, 1_000_000, 4), vec![1000, 10_000, 100_000, 1_000_000]);
+        assert_eq!(log_spaced_sizes(1, 4, 10), vec![1, 2, 3, 4]);
+        assert_eq!(log_spaced_sizes(50, 50, 10), vec![50]);
+    }
+
+    #[test]
+    fn ranges_are_parsed() {
+        assert_eq!(parse_range("1000..1000000"), Ok((1000, 1_000_000)));
+        assert!(parse_range("1000").is_err());
+        assert!(parse_range("10..1").is_err());
+        assert!(parse_range("0..10").is_err());
+    }
+
+    #[test]
+    fn fit_recovers_model_and_constant() {
+        let observations: Vec<Observation> = [(1000usize, 8usize), (10_000, 32), (100_000, 128)]
+            .iter()
+            .map(|&(n, h)| Observation { n, h, cost: 3.0 * n as f64 * h as f64 })
+            .collect();
+        let fits = fit(&observations);
+        assert_eq!(fits[0].model, Model::NH);
+        assert!((fits[0].constant - 3.0).abs() < 1e-9);
+        assert!(fits[0].relative_error < 1e-9);
+
+        let observations: Vec<Observation> = [1000usize, 10_000, 100_000]
+            .iter()
+            .map(|&n| Observation { n, h: n, cost: 0.5 * n as f64 * (n as f64).log2() })
+            .collect();
+        let fits = fit(&observations);
+        assert!(fits[0].model == Model::NLogN || fits[0].model == Model::NLogH);
+        assert!((fits[0].constant - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_fit_without_costs() {
+        let observations: Vec<Observation> = [1usize, 2].iter().map(|&n| Observation { n, h: n, cost: 0.0 }).collect();
+        assert!(fit(&observations).is_empty());
+        assert!(fit(&[]).is_empty());
+    }
+}